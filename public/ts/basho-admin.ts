@@ -1,29 +1,24 @@
 import { adminTrigger, alertSendStats } from "./push.js";
 
-document.querySelectorAll(".bestow-emperors-cup-button").forEach((button) => {
-  button.addEventListener("click", () => {
-    void postCup(button, true);
-  });
-});
-document.querySelectorAll(".revoke-emperors-cup-button").forEach((button) => {
-  button.addEventListener("click", () => {
-    void postCup(button, false);
-  });
+const awardForm = document.querySelector(
+  ".award-form",
+) as HTMLFormElement | null;
+awardForm?.addEventListener("submit", (event) => {
+  event.preventDefault();
+  const submitter = event.submitter as HTMLButtonElement | null;
+  void postAward(awardForm, submitter?.name === "revoke" ? "revoke" : "bestow");
 });
 
-async function postCup(button: Element, bestow: boolean): Promise<void> {
-  if (
-    !(button instanceof HTMLButtonElement) ||
-    button.dataset.playerId === undefined
-  ) {
-    throw new Error(`unexpected button element: ${button.tagName}`);
-  }
+async function postAward(
+  form: HTMLFormElement,
+  action: "bestow" | "revoke",
+): Promise<void> {
+  const formData = new FormData(form);
   const data = {
-    player_id: parseInt(button.dataset.playerId),
+    award: formData.get("award"),
+    player_id: parseInt(formData.get("player_id") as string),
   };
-  const url =
-    location.href + "/" + (bestow ? "bestow" : "revoke") + "_emperors_cup";
-  const response = await fetch(url, {
+  const response = await fetch(`/basho/${bashoId}/${action}_award`, {
     method: "POST",
     body: JSON.stringify(data),
     headers: new Headers({
@@ -32,7 +27,7 @@ async function postCup(button: Element, bestow: boolean): Promise<void> {
     credentials: "same-origin",
   });
   if (response.ok) {
-    alert("Emperor's Cup has been " + (bestow ? "bestowed" : "revoked"));
+    alert(`Award has been ${action === "bestow" ? "bestowed" : "revoked"}`);
   } else {
     const text = await response.text();
     alert("error: " + text);
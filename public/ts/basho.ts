@@ -15,6 +15,7 @@ for (const el of document.querySelectorAll(".select-radio")) {
       ) as HTMLElement;
       label.classList.toggle("is-player-pick", otherRadio === radio);
     }
+    updateCaptainOptions();
     // savePicks();
   });
 }
@@ -22,6 +23,11 @@ for (const el of document.querySelectorAll(".select-radio")) {
 pickForm.addEventListener("submit", (event) => {
   event.preventDefault();
   const formData = new FormData(pickForm);
+  for (const name of ["captain", "yusho"]) {
+    if (formData.get(name) === "") {
+      formData.delete(name);
+    }
+  }
   const url = pickForm.action;
   setSelectable(false);
   void (async function () {
@@ -40,12 +46,63 @@ for (const button of document.querySelectorAll(".change-picks-button")) {
   });
 }
 
+for (const button of document.querySelectorAll(".last-picks-button")) {
+  button.addEventListener("click", (event) => {
+    event.preventDefault();
+    applyLastPicks();
+  });
+}
+
+function applyLastPicks(): void {
+  const lastPicks = document.getElementById("last-picks");
+  if (lastPicks === null) return;
+  setSelectable(true);
+  const ids = (lastPicks.dataset.rikishiIds ?? "").split(",");
+  for (const id of ids.filter((id) => id !== "")) {
+    const radio = document.getElementById(
+      `banzuke-rikishi-${id}`,
+    ) as HTMLInputElement | null;
+    if (radio !== null) {
+      radio.checked = true;
+      radio.dispatchEvent(new Event("change"));
+    }
+  }
+  lastPicks.hidden = false;
+  lastPicks.scrollIntoView();
+}
+
+// Captain choices are the currently selected picks.
+function updateCaptainOptions(): void {
+  const select = pickForm.querySelector(
+    ".captain-select",
+  ) as HTMLSelectElement | null;
+  if (select === null) return;
+  const current = select.value;
+  select.replaceChildren(new Option("No captain", ""));
+  for (const el of pickForm.querySelectorAll(".select-radio:checked")) {
+    const radio = el as HTMLInputElement;
+    select.add(
+      new Option(
+        radio.dataset.rikishiName ?? radio.value,
+        radio.value,
+        false,
+        radio.value === current,
+      ),
+    );
+  }
+}
+
 function setSelectable(selectable: boolean): void {
   banzukeSection.classList.toggle("selectable", selectable);
   for (const el of document.querySelectorAll(".select-radio")) {
     const button = el as HTMLInputElement;
     button.disabled = !selectable;
   }
+  for (const el of document.querySelectorAll(
+    ".captain-select, .yusho-select",
+  )) {
+    (el as HTMLSelectElement).disabled = !selectable;
+  }
 }
 
 async function savePicks(formData: FormData, url: string): Promise<boolean> {
@@ -8,6 +8,8 @@ declare global {
     banzuke: HTMLInputElement;
     venue: HTMLInputElement;
     start_date: HTMLInputElement;
+    sealed_picks: HTMLInputElement;
+    captain_picks: HTMLInputElement;
     notify_kyujyo: HTMLInputElement;
   }
 }
@@ -66,6 +68,8 @@ bashoForm.addEventListener("submit", (event) => {
   const data = {
     venue: bashoForm.elements.venue.value,
     start_date: bashoForm.elements.start_date.value,
+    sealed_picks: bashoForm.elements.sealed_picks.checked,
+    captain_picks: bashoForm.elements.captain_picks.checked,
     banzuke: parsedBanzuke,
     notify_kyujyo: bashoForm.elements.notify_kyujyo.checked,
   };
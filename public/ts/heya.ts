@@ -16,3 +16,37 @@ for (const form of expelForms) {
     }
   });
 }
+
+const transferForms: NodeListOf<HTMLFormElement> =
+  document.body.querySelectorAll("form.transfer");
+for (const form of transferForms) {
+  form.addEventListener("submit", (event) => {
+    const { member, heya } = form.dataset;
+    if (heya === undefined || member === undefined)
+      throw new Error("missing heya or member name");
+
+    if (
+      !confirm(
+        `Are you sure you want to make ${member} the oyakata of ${heya}? You will remain a member.`,
+      )
+    ) {
+      event.preventDefault();
+    }
+  });
+}
+
+const dissolveForm = document.body.querySelector<HTMLFormElement>(
+  "form.dissolve",
+);
+dissolveForm?.addEventListener("submit", (event) => {
+  const { heya } = dissolveForm.dataset;
+  if (heya === undefined) throw new Error("missing heya name");
+
+  if (
+    !confirm(
+      `Are you sure you want to dissolve ${heya}? This removes all members and can’t be undone.`,
+    )
+  ) {
+    event.preventDefault();
+  }
+});
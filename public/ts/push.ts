@@ -3,6 +3,7 @@
 export type BashoId = string;
 export type PlayerId = number;
 export type Day = number;
+export type HeyaId = number;
 
 export type PushType =
   | { Test: undefined }
@@ -10,7 +11,13 @@ export type PushType =
   | { EntriesOpen: BashoId }
   | { BashoStartCountdown: BashoId }
   | { DayResult: [BashoId, PlayerId, Day] }
-  | { BashoResult: [BashoId, PlayerId] };
+  | { BashoResult: [BashoId, PlayerId] }
+  | { HeyaMessage: [HeyaId, string] }
+  | { HeyaPost: number }
+  | { HeyaAdded: [HeyaId, PlayerId] }
+  | { HeyaRemoved: [HeyaId, PlayerId] }
+  | { HeyaDissolved: [string, PlayerId] }
+  | { HeyaStandings: [BashoId, PlayerId, Day] };
 
 export interface RikishiDayResult {
   name: string;
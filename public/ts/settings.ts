@@ -9,6 +9,7 @@ import {
 interface FormControls extends HTMLCollectionBase {
   // [item: string]: HTMLElement | RadioNodeList
   name: HTMLInputElement;
+  "auto-pick": HTMLSelectElement;
   notifications: HTMLFieldSetElement;
   "test-notification": HTMLButtonElement;
 }
@@ -17,6 +18,7 @@ const form = document.getElementById("settings-form") as HTMLFormElement;
 const messages = form.querySelector(".messages") as HTMLElement;
 const saveButton = form.querySelector(".save-button") as HTMLButtonElement;
 const nameField = (form.elements as unknown as FormControls).name;
+const autoPickField = (form.elements as unknown as FormControls)["auto-pick"];
 const testNotificationButton = (form.elements as unknown as FormControls)[
   "test-notification"
 ];
@@ -68,6 +70,7 @@ function refreshBusyState(busy: boolean): void {
   saveButton.disabled = busy || !edited;
   notifications.disabled = busy;
   nameField.disabled = busy;
+  autoPickField.disabled = busy;
   testNotificationButton.disabled = busy || subscriptionState == null;
 }
 
@@ -85,6 +88,7 @@ async function save(): Promise<void> {
       name: nameField.value,
       push_subscription: pushSubscription?.toJSON(),
       notification_opt_in: optIn,
+      auto_pick: autoPickField.value !== "" ? autoPickField.value : null,
     };
     const resp = await fetch("/settings", {
       method: "POST",
@@ -0,0 +1,2 @@
+-- Awards bestowed by an admin, which re-finalizing a basho must leave alone.
+ALTER TABLE award ADD COLUMN is_manual INTEGER NOT NULL DEFAULT 0;
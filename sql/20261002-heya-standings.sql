@@ -0,0 +1,11 @@
+-- Snapshot of each heya's league score at finalize time, since heya membership changes over time.
+CREATE TABLE heya_basho_result (
+    basho_id        INTEGER NOT NULL REFERENCES basho(id) ON DELETE CASCADE,
+    heya_id         INTEGER NOT NULL REFERENCES heya(id) ON DELETE CASCADE,
+    total           INTEGER NOT NULL,
+    rank            INTEGER NOT NULL,
+    entrants        INTEGER NOT NULL,
+
+    PRIMARY KEY (basho_id, heya_id)
+);
+CREATE INDEX heya_basho_result__heya_id ON heya_basho_result (heya_id);
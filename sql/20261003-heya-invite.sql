@@ -0,0 +1,16 @@
+CREATE TABLE heya_invite (
+    token               TEXT PRIMARY KEY,
+    heya_id             INTEGER NOT NULL REFERENCES heya(id) ON DELETE CASCADE,
+    created_by          INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    create_date         TEXT NOT NULL,
+    expire_date         TEXT NOT NULL
+);
+CREATE INDEX heya_invite__heya_id ON heya_invite (heya_id);
+
+CREATE TABLE heya_join_request (
+    heya_id       INTEGER NOT NULL REFERENCES heya(id) ON DELETE CASCADE,
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    request_date  TEXT NOT NULL,
+    PRIMARY KEY (heya_id, player_id) ON CONFLICT IGNORE
+);
+CREATE INDEX heya_join_request__player_id ON heya_join_request (player_id);
@@ -0,0 +1 @@
+ALTER TABLE heya_player ADD COLUMN is_cohost INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Slugs a heya used before being renamed, so old links keep working.
+CREATE TABLE heya_slug_history (
+    slug          TEXT PRIMARY KEY,
+    heya_id       INTEGER NOT NULL REFERENCES heya(id) ON DELETE CASCADE,
+    retire_date   TEXT NOT NULL
+);
+CREATE INDEX heya_slug_history__heya_id ON heya_slug_history (heya_id);
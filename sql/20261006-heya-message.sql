@@ -0,0 +1,8 @@
+-- Oyakata messages pushed to heya members, kept to limit how often they can be sent.
+CREATE TABLE heya_message (
+    heya_id       INTEGER NOT NULL REFERENCES heya(id) ON DELETE CASCADE,
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    message       TEXT NOT NULL,
+    send_date     TEXT NOT NULL
+);
+CREATE INDEX heya_message__heya_id ON heya_message (heya_id, send_date);
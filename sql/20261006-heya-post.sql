@@ -0,0 +1,10 @@
+CREATE TABLE heya_post (
+    id            INTEGER PRIMARY KEY,
+    heya_id       INTEGER NOT NULL REFERENCES heya(id) ON DELETE CASCADE,
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    basho_id      INTEGER, -- NULL for the general thread
+    body          TEXT NOT NULL,
+    post_date     TEXT NOT NULL,
+    is_pinned     INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX heya_post__heya_id ON heya_post (heya_id, basho_id);
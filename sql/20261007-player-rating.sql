@@ -0,0 +1,9 @@
+-- Elo-style skill rating after each basho. Recomputed from scratch by the admin backfill.
+CREATE TABLE player_rating (
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    basho_id      INTEGER NOT NULL REFERENCES basho(id) ON DELETE CASCADE,
+    rating        REAL NOT NULL,
+
+    PRIMARY KEY (player_id, basho_id)
+);
+CREATE INDEX player_rating__basho_id ON player_rating (basho_id);
@@ -0,0 +1,3 @@
+-- Why each player moved to their rank (see RankReason) and whether an Ozeki is kadoban. Rows written before banzuke-style promotion have no reason.
+ALTER TABLE player_rank ADD COLUMN reason INTEGER;
+ALTER TABLE player_rank ADD COLUMN is_kadoban INTEGER NOT NULL DEFAULT 0;
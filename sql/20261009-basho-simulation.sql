@@ -0,0 +1,10 @@
+-- Monte Carlo odds for each entrant, recomputed whenever a day's torikumi is updated.
+CREATE TABLE basho_simulation (
+    basho_id            INTEGER NOT NULL REFERENCES basho(id) ON DELETE CASCADE,
+    day                 INTEGER NOT NULL,
+    player_id           INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    win_probability     REAL NOT NULL,
+    top_probability     REAL NOT NULL,
+
+    PRIMARY KEY (basho_id, day, player_id)
+);
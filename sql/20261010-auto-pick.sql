@@ -0,0 +1,8 @@
+-- Players who opted in to having empty rank-group slots filled at the deadline, and with which AutoPickStrategy.
+CREATE TABLE player_auto_pick (
+    player_id     INTEGER PRIMARY KEY REFERENCES player(id) ON DELETE CASCADE,
+    strategy      INTEGER NOT NULL
+);
+
+-- Picks made by fill_auto_picks rather than by the player.
+ALTER TABLE pick ADD COLUMN is_auto INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,29 @@
+-- Every save of a player's picks, so edits can be reviewed after the fact. `pick` keeps only the latest version.
+CREATE TABLE pick_version (
+    id            INTEGER PRIMARY KEY,
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    basho_id      INTEGER NOT NULL REFERENCES basho(id) ON DELETE CASCADE,
+    version       INTEGER NOT NULL,
+    save_date     TEXT, -- NULL for picks saved before history was kept
+    is_auto       INTEGER NOT NULL DEFAULT 0,
+
+    UNIQUE (player_id, basho_id, version)
+);
+CREATE INDEX pick_version__basho_id ON pick_version (basho_id);
+
+CREATE TABLE pick_version_rikishi (
+    pick_version_id INTEGER NOT NULL REFERENCES pick_version(id) ON DELETE CASCADE,
+    rikishi_id      INTEGER NOT NULL REFERENCES rikishi(id) ON DELETE CASCADE,
+    is_auto         INTEGER NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (pick_version_id, rikishi_id)
+);
+
+-- Seed the history with the picks as they stand.
+INSERT INTO pick_version (player_id, basho_id, version, save_date)
+SELECT DISTINCT player_id, basho_id, 1, NULL FROM pick;
+
+INSERT INTO pick_version_rikishi (pick_version_id, rikishi_id, is_auto)
+SELECT v.id, pick.rikishi_id, pick.is_auto
+FROM pick
+JOIN pick_version AS v USING (player_id, basho_id);
@@ -0,0 +1,2 @@
+-- Hide other players' picks and pick counts until the basho starts.
+ALTER TABLE basho ADD COLUMN sealed_picks INTEGER NOT NULL DEFAULT 0;
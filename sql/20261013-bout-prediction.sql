@@ -0,0 +1,12 @@
+-- Side game: players predict the winner of each scheduled makuuchi bout for a day. Scheduled bouts are stored in torikumi with a NULL win until fought.
+CREATE TABLE bout_prediction (
+    player_id       INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    basho_id        INTEGER NOT NULL REFERENCES basho(id) ON DELETE CASCADE,
+    day             INTEGER NOT NULL,
+    rikishi_id      INTEGER NOT NULL REFERENCES rikishi(id) ON DELETE CASCADE, -- predicted winner
+    prediction_date TEXT NOT NULL,
+    is_correct      INTEGER, -- NULL until the bout is fought, and for bouts that never happen
+
+    PRIMARY KEY (player_id, basho_id, day, rikishi_id)
+);
+CREATE INDEX bout_prediction__basho_id_day ON bout_prediction (basho_id, day);
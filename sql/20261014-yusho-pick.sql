@@ -0,0 +1,12 @@
+-- Each player's optional prediction of the basho's yusho winner, saved with their picks. A correct prediction breaks ties on points.
+CREATE TABLE yusho_pick (
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    basho_id      INTEGER NOT NULL REFERENCES basho(id) ON DELETE CASCADE,
+    rikishi_id    INTEGER NOT NULL,
+
+    PRIMARY KEY (player_id, basho_id),
+    FOREIGN KEY (rikishi_id, basho_id) REFERENCES banzuke(rikishi_id, basho_id) ON DELETE CASCADE
+);
+
+-- Recorded by an admin, or at finalization when one rikishi has the most wins outright.
+ALTER TABLE basho ADD COLUMN yusho_rikishi_id INTEGER NULL REFERENCES rikishi(id);
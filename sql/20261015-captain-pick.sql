@@ -0,0 +1,17 @@
+-- Optional rule: each player marks one pick as captain and that rikishi's wins count double.
+ALTER TABLE basho ADD COLUMN captain_picks INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pick ADD COLUMN is_captain INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pick_version_rikishi ADD COLUMN is_captain INTEGER NOT NULL DEFAULT 0;
+
+DROP VIEW basho_score;
+CREATE VIEW basho_score (basho_id, player_id, wins)
+AS SELECT
+    pick.basho_id,
+    pick.player_id,
+    COALESCE(SUM(torikumi.win * (1 + (pick.is_captain AND basho.captain_picks))), 0) AS wins
+FROM pick
+JOIN basho ON basho.id = pick.basho_id
+LEFT JOIN torikumi
+    ON torikumi.rikishi_id = pick.rikishi_id
+    AND torikumi.basho_id = pick.basho_id
+GROUP BY pick.basho_id, pick.player_id;
@@ -0,0 +1,40 @@
+-- Heya draft mode: members take turns in snake order drafting rikishi for a basho, and each rikishi belongs to only one member.
+CREATE TABLE heya_draft (
+    heya_id             INTEGER NOT NULL REFERENCES heya(id) ON DELETE CASCADE,
+    basho_id            INTEGER NOT NULL REFERENCES basho(id) ON DELETE CASCADE,
+    pick_minutes        INTEGER NOT NULL,
+    rounds              INTEGER NOT NULL,
+    create_date         TEXT NOT NULL,
+    -- When the member currently on the clock started their turn.
+    turn_start_date     TEXT NOT NULL,
+
+    PRIMARY KEY (heya_id, basho_id)
+);
+
+-- Members in draft order, fixed when the draft starts.
+CREATE TABLE heya_draft_member (
+    heya_id             INTEGER NOT NULL,
+    basho_id            INTEGER NOT NULL,
+    player_id           INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    position            INTEGER NOT NULL,
+
+    PRIMARY KEY (heya_id, basho_id, player_id),
+    UNIQUE (heya_id, basho_id, position),
+    FOREIGN KEY (heya_id, basho_id) REFERENCES heya_draft(heya_id, basho_id) ON DELETE CASCADE
+);
+
+CREATE TABLE heya_draft_pick (
+    heya_id             INTEGER NOT NULL,
+    basho_id            INTEGER NOT NULL,
+    pick_number         INTEGER NOT NULL,
+    player_id           INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    rikishi_id          INTEGER NOT NULL,
+    pick_date           TEXT NOT NULL,
+    -- Made for the member when their turn's timer ran out.
+    is_auto             INTEGER NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (heya_id, basho_id, pick_number),
+    UNIQUE (heya_id, basho_id, rikishi_id),
+    FOREIGN KEY (heya_id, basho_id) REFERENCES heya_draft(heya_id, basho_id) ON DELETE CASCADE,
+    FOREIGN KEY (rikishi_id, basho_id) REFERENCES banzuke(rikishi_id, basho_id) ON DELETE CASCADE
+);
@@ -0,0 +1,14 @@
+-- Each player's predicted makuuchi banzuke for the basho after a finalized one. The predicted basho usually has no row in `basho` yet, so basho_id isn't a foreign key.
+CREATE TABLE banzuke_prediction (
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    basho_id      INTEGER NOT NULL,
+    rikishi_id    INTEGER NOT NULL REFERENCES rikishi(id),
+    rank          TEXT NOT NULL,
+    -- Banzuke slots between the predicted and actual rank, set once the real banzuke is imported.
+    distance      INTEGER NULL,
+
+    PRIMARY KEY (player_id, basho_id, rikishi_id),
+    UNIQUE (player_id, basho_id, rank)
+);
+
+CREATE INDEX banzuke_prediction__basho_id ON banzuke_prediction (basho_id);
@@ -0,0 +1,23 @@
+-- Featured rikishi for the over/under side game. A line of 8 is shown as 8.5: "over" needs 9 or more wins, so there are no pushes.
+CREATE TABLE win_line (
+    basho_id      INTEGER NOT NULL,
+    rikishi_id    INTEGER NOT NULL,
+    line          INTEGER NOT NULL,
+
+    PRIMARY KEY (basho_id, rikishi_id),
+    FOREIGN KEY (rikishi_id, basho_id) REFERENCES banzuke(rikishi_id, basho_id) ON DELETE CASCADE
+);
+
+CREATE TABLE win_line_pick (
+    player_id     INTEGER NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    basho_id      INTEGER NOT NULL,
+    rikishi_id    INTEGER NOT NULL,
+    is_over       INTEGER NOT NULL,
+    -- Set when the basho is finalized.
+    is_correct    INTEGER NULL,
+
+    PRIMARY KEY (player_id, basho_id, rikishi_id),
+    FOREIGN KEY (basho_id, rikishi_id) REFERENCES win_line(basho_id, rikishi_id) ON DELETE CASCADE
+);
+
+CREATE INDEX win_line_pick__basho_id ON win_line_pick (basho_id);
@@ -0,0 +1,335 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{Connection, OptionalExtension, Result as SqlResult, ToSql, Transaction};
+use std::collections::{HashMap, HashSet};
+
+use super::basho::SAVE_PICKS_GRACE_PERIOD_MINUTES;
+use super::pick_history::PickVersion;
+use super::{BashoId, PlayerId, Rank, RankGroup, Result, RikishiId};
+
+/// How empty rank-group slots are filled at the deadline for players who opted in. The numeric values are stored in the `player_auto_pick.strategy` column so they must never be reused.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum AutoPickStrategy {
+    RepeatLastBasho = 1,
+    HighestRank = 2,
+    MostPopular = 3,
+}
+
+impl AutoPickStrategy {
+    pub fn all() -> [Self; 3] {
+        [Self::RepeatLastBasho, Self::HighestRank, Self::MostPopular]
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::RepeatLastBasho => {
+                "Repeat your last basho’s pick if that rikishi is still in the same group, otherwise the highest-ranked rikishi"
+            }
+            Self::HighestRank => "The highest-ranked rikishi in the group",
+            Self::MostPopular => "The rikishi most other players picked in the group",
+        }
+    }
+
+    fn from_i64(num: i64) -> Option<Self> {
+        match num {
+            1 => Some(Self::RepeatLastBasho),
+            2 => Some(Self::HighestRank),
+            3 => Some(Self::MostPopular),
+            _ => None,
+        }
+    }
+
+    pub fn for_player(db: &Connection, player_id: PlayerId) -> Result<Option<Self>> {
+        Ok(db
+            .query_row(
+                "SELECT strategy FROM player_auto_pick WHERE player_id = ?",
+                params![player_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn set_for_player(
+        txn: &Transaction,
+        player_id: PlayerId,
+        strategy: Option<Self>,
+    ) -> Result<()> {
+        match strategy {
+            Some(strategy) => txn.execute(
+                "
+                    INSERT INTO player_auto_pick (player_id, strategy)
+                    VALUES (?, ?)
+                    ON CONFLICT (player_id) DO UPDATE SET
+                        strategy = excluded.strategy
+                ",
+                params![player_id, strategy],
+            )?,
+            None => txn.execute(
+                "DELETE FROM player_auto_pick WHERE player_id = ?",
+                params![player_id],
+            )?,
+        };
+        Ok(())
+    }
+}
+
+impl FromSql for AutoPickStrategy {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        value
+            .as_i64()
+            .and_then(|num| Self::from_i64(num).ok_or(FromSqlError::OutOfRange(num)))
+    }
+}
+
+impl ToSql for AutoPickStrategy {
+    fn to_sql(&self) -> SqlResult<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(*self as u8))
+    }
+}
+
+struct Candidate {
+    id: RikishiId,
+    rank: Rank,
+    /// How many players picked this rikishi themselves.
+    popularity: u32,
+}
+
+/// Chooses a rikishi for one empty group. `candidates` are the group's non-kyujyo rikishi and `last_basho_picks` maps the player's picks in their last basho to the group each was in then.
+fn choose(
+    strategy: AutoPickStrategy,
+    group: RankGroup,
+    candidates: &[Candidate],
+    last_basho_picks: &HashMap<RikishiId, RankGroup>,
+) -> Option<RikishiId> {
+    let highest_rank = || candidates.iter().min_by_key(|c| c.rank);
+    match strategy {
+        AutoPickStrategy::RepeatLastBasho => candidates
+            .iter()
+            .filter(|c| last_basho_picks.get(&c.id) == Some(&group))
+            .min_by_key(|c| c.rank)
+            .or_else(highest_rank),
+        AutoPickStrategy::HighestRank => highest_rank(),
+        AutoPickStrategy::MostPopular => candidates
+            .iter()
+            .filter(|c| c.popularity > 0)
+            // Ties go to the higher rank, which sorts lower.
+            .max_by_key(|c| (c.popularity, std::cmp::Reverse(c.rank)))
+            .or_else(highest_rank),
+    }
+    .map(|c| c.id)
+}
+
+/// Fills the empty rank-group slots of every opted-in player who entered this basho or the previous one, once the pick deadline has passed. Only empty slots are touched, so running it again is harmless. Returns how many picks were added.
+pub fn fill_auto_picks(txn: &Transaction, basho_id: BashoId) -> Result<usize> {
+    let start_date: DateTime<Utc> = txn.query_row(
+        "SELECT start_date FROM basho WHERE id = ?",
+        params![basho_id],
+        |row| row.get(0),
+    )?;
+    if start_date + TimeDelta::minutes(SAVE_PICKS_GRACE_PERIOD_MINUTES) > Utc::now() {
+        return Ok(0);
+    }
+
+    let mut candidates: HashMap<RankGroup, Vec<Candidate>> = HashMap::new();
+    {
+        let mut stmt = txn.prepare(
+            "
+                SELECT
+                    b.rikishi_id,
+                    b.rank,
+                    (
+                        SELECT COUNT(*)
+                        FROM pick AS p
+                        WHERE p.basho_id = b.basho_id AND p.rikishi_id = b.rikishi_id AND NOT p.is_auto
+                    ) AS popularity
+                FROM banzuke AS b
+                WHERE b.basho_id = ? AND NOT b.kyujyo
+            ",
+        )?;
+        let rows = stmt.query_map(params![basho_id], |row| {
+            Ok(Candidate {
+                id: row.get(0)?,
+                rank: row.get(1)?,
+                popularity: row.get(2)?,
+            })
+        })?;
+        for candidate in rows {
+            let candidate = candidate?;
+            let group = candidate.rank.group();
+            if group.as_index() < RankGroup::count() {
+                candidates.entry(group).or_default().push(candidate);
+            }
+        }
+    }
+
+    let last_basho_id = basho_id.incr(-1);
+    let players: Vec<(PlayerId, AutoPickStrategy)> = txn
+        .prepare(
+            "
+                SELECT pap.player_id, pap.strategy
+                FROM player_auto_pick AS pap
+                WHERE EXISTS (
+                    SELECT 1
+                    FROM pick
+                    WHERE pick.player_id = pap.player_id AND pick.basho_id IN (:basho_id, :last_basho_id)
+                )
+            ",
+        )?
+        .query_map(
+            named_params! {":basho_id": basho_id, ":last_basho_id": last_basho_id},
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?
+        .collect::<SqlResult<_>>()?;
+
+    let mut pick_stmt =
+        txn.prepare("SELECT rikishi_id FROM pick WHERE player_id = ? AND basho_id = ?")?;
+    let mut rank_stmt =
+        txn.prepare("SELECT rank FROM banzuke WHERE basho_id = ? AND rikishi_id = ?")?;
+    let mut last_basho_stmt = txn.prepare(
+        "
+            SELECT pick.rikishi_id, banzuke.rank
+            FROM pick
+            JOIN banzuke USING (basho_id, rikishi_id)
+            WHERE pick.player_id = :player_id AND pick.basho_id = (
+                SELECT MAX(basho_id)
+                FROM pick
+                WHERE player_id = :player_id AND basho_id < :basho_id
+            )
+        ",
+    )?;
+    let mut insert = txn.prepare(
+        "
+            INSERT INTO pick (player_id, basho_id, rikishi_id, is_auto)
+            VALUES (?, ?, ?, 1)
+        ",
+    )?;
+    let mut added = 0;
+    for (player_id, strategy) in players {
+        let mut filled = HashSet::new();
+        for rikishi_id in pick_stmt
+            .query_map(params![player_id, basho_id], |row| {
+                row.get::<_, RikishiId>(0)
+            })?
+            .collect::<SqlResult<Vec<_>>>()?
+        {
+            let rank: Rank =
+                rank_stmt.query_row(params![basho_id, rikishi_id], |row| row.get(0))?;
+            filled.insert(rank.group());
+        }
+        let last_basho_picks: HashMap<RikishiId, RankGroup> = last_basho_stmt
+            .query_map(
+                named_params! {":player_id": player_id, ":basho_id": basho_id},
+                |row| Ok((row.get(0)?, row.get::<_, Rank>(1)?.group())),
+            )?
+            .collect::<SqlResult<_>>()?;
+
+        let added_before = added;
+        for index in 0..RankGroup::count() {
+            let group = RankGroup::from_index(index);
+            if filled.contains(&group) {
+                continue;
+            }
+            let group_candidates = candidates.get(&group).map_or(&[][..], Vec::as_slice);
+            if let Some(rikishi_id) = choose(strategy, group, group_candidates, &last_basho_picks) {
+                debug!(
+                    "auto-picking {} for player {} group {} in {}",
+                    rikishi_id, player_id, group, basho_id
+                );
+                insert.execute(params![player_id, basho_id, rikishi_id])?;
+                added += 1;
+            }
+        }
+        if added > added_before {
+            PickVersion::record(txn, player_id, basho_id, true)?;
+        }
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::testing;
+    use std::str::FromStr;
+
+    fn candidate(id: RikishiId, rank: &str, popularity: u32) -> Candidate {
+        Candidate {
+            id,
+            rank: Rank::from_str(rank).unwrap(),
+            popularity,
+        }
+    }
+
+    #[test]
+    fn strategies_pick_expected_rikishi() {
+        let candidates = [
+            candidate(1, "M6e", 2),
+            candidate(2, "M7w", 9),
+            candidate(3, "M9e", 0),
+        ];
+        let group = candidates[0].rank.group();
+        let last_basho = HashMap::from([(3, group), (42, group)]);
+        let none = HashMap::new();
+        assert_eq!(
+            choose(
+                AutoPickStrategy::HighestRank,
+                group,
+                &candidates,
+                &last_basho
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            choose(
+                AutoPickStrategy::MostPopular,
+                group,
+                &candidates,
+                &last_basho
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            choose(
+                AutoPickStrategy::RepeatLastBasho,
+                group,
+                &candidates,
+                &last_basho
+            ),
+            Some(3)
+        );
+        assert_eq!(
+            choose(AutoPickStrategy::RepeatLastBasho, group, &candidates, &none),
+            Some(1)
+        );
+        // Rikishi 2 was picked in a higher group last basho, so picking them now isn't a repeat.
+        let moved = HashMap::from([(2, Rank::from_str("M3e").unwrap().group())]);
+        assert_eq!(
+            choose(
+                AutoPickStrategy::RepeatLastBasho,
+                group,
+                &candidates,
+                &moved
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            choose(AutoPickStrategy::HighestRank, group, &[], &last_basho),
+            None
+        );
+    }
+
+    #[test]
+    fn strategy_saves_and_reads_back() {
+        let mut db = testing::test_db();
+        testing::insert_player(&db, 1, "kotoshogiku");
+        // The settings page posts the selected option's value, which is the strategy's `Debug` name.
+        let posted: AutoPickStrategy =
+            serde_json::from_str(&format!("\"{:?}\"", AutoPickStrategy::MostPopular)).unwrap();
+        for strategy in [Some(posted), None] {
+            let txn = db.transaction().unwrap();
+            AutoPickStrategy::set_for_player(&txn, 1, strategy).unwrap();
+            txn.commit().unwrap();
+            assert_eq!(AutoPickStrategy::for_player(&db, 1).unwrap(), strategy);
+        }
+    }
+}
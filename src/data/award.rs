@@ -4,28 +4,96 @@ use rusqlite::{Connection, ToSql};
 use std::fmt::Display;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Copy, Clone, serde::Serialize)]
+/// Awards bestowed at the end of each basho, modeled after the yusho and sansho prizes. The numeric values are stored in the `award.type` column so they must never be reused.
+#[derive(
+    Debug, PartialEq, PartialOrd, Eq, Ord, Copy, Clone, Hash, serde::Serialize, serde::Deserialize,
+)]
 pub enum Award {
     EmperorsCup = 1,
+    JunYusho = 2,
+    OutstandingPerformance = 3,
+    Technique = 4,
+    FightingSpirit = 5,
+    BanzukePrediction = 6,
 }
 
 impl Award {
+    pub fn all() -> [Self; 6] {
+        [
+            Award::EmperorsCup,
+            Award::JunYusho,
+            Award::OutstandingPerformance,
+            Award::Technique,
+            Award::FightingSpirit,
+            Award::BanzukePrediction,
+        ]
+    }
+
     pub fn emoji(self) -> &'static str {
         match self {
             Award::EmperorsCup => "🏆",
+            Award::JunYusho => "🥈",
+            Award::OutstandingPerformance => "🚀",
+            Award::Technique => "🎯",
+            Award::FightingSpirit => "🔥",
+            Award::BanzukePrediction => "📜",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Award::EmperorsCup => "Emperor’s Cup",
+            Award::JunYusho => "Jun-Yusho",
+            Award::OutstandingPerformance => "Outstanding Performance Prize",
+            Award::Technique => "Technique Prize",
+            Award::FightingSpirit => "Fighting Spirit Prize",
+            Award::BanzukePrediction => "Banzuke Prize",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Award::EmperorsCup => "Highest score of the basho.",
+            Award::JunYusho => "Runner-up score of the basho.",
+            Award::OutstandingPerformance => {
+                "Finished furthest above the place expected from their player rank."
+            }
+            Award::Technique => {
+                "Best score when each win is weighted by how few other players picked that rikishi."
+            }
+            Award::FightingSpirit => "Best score among players entering their first basho.",
+            Award::BanzukePrediction => {
+                "Closest prediction of the basho’s makuuchi banzuke, made after the previous basho."
+            }
         }
     }
 
-    pub fn _bestow(
+    fn from_i64(num: i64) -> Option<Self> {
+        match num {
+            1 => Some(Award::EmperorsCup),
+            2 => Some(Award::JunYusho),
+            3 => Some(Award::OutstandingPerformance),
+            4 => Some(Award::Technique),
+            5 => Some(Award::FightingSpirit),
+            6 => Some(Award::BanzukePrediction),
+            _ => None,
+        }
+    }
+
+    pub fn bestow(
         self,
         db: &mut Connection,
         basho_id: BashoId,
         player_id: PlayerId,
     ) -> Result<(), DataError> {
+        info!(
+            "bestowing {:?} on player {} for {}",
+            self, player_id, basho_id
+        );
         db.prepare(
             "
-                INSERT INTO award (basho_id, type, player_id)
-                VALUES (?, ?, ?)
+                INSERT INTO award (basho_id, type, player_id, is_manual)
+                VALUES (?, ?, ?, 1)
             ",
         )?
         .execute(params![basho_id, self, player_id])
@@ -33,12 +101,16 @@ impl Award {
         .map_err(|e| e.into())
     }
 
-    pub fn _revoke(
+    pub fn revoke(
         self,
         db: &mut Connection,
         basho_id: BashoId,
         player_id: PlayerId,
     ) -> Result<(), DataError> {
+        info!(
+            "revoking {:?} from player {} for {}",
+            self, player_id, basho_id
+        );
         db.prepare(
             "
                 DELETE FROM award
@@ -58,7 +130,7 @@ impl Award {
             if string.is_empty() {
                 vec![]
             } else {
-                string
+                let mut awards: Vec<Self> = string
                     .split(',')
                     .filter_map(|a| match a.parse() {
                         Err(e) => {
@@ -67,7 +139,9 @@ impl Award {
                         }
                         Ok(award) => Some(award),
                     })
-                    .collect()
+                    .collect();
+                awards.sort();
+                awards
             }
         } else {
             vec![]
@@ -85,19 +159,18 @@ impl FromStr for Award {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "1" => Ok(Award::EmperorsCup),
-            _ => Err(format!("unknown award type {}", s)),
-        }
+        s.parse()
+            .ok()
+            .and_then(Self::from_i64)
+            .ok_or_else(|| format!("unknown award type {}", s))
     }
 }
 
 impl FromSql for Award {
     fn column_result(value: ValueRef) -> FromSqlResult<Self> {
-        value.as_i64().and_then(|num| match num {
-            1 => Ok(Award::EmperorsCup),
-            _ => Err(FromSqlError::OutOfRange(num)),
-        })
+        value
+            .as_i64()
+            .and_then(|num| Self::from_i64(num).ok_or(FromSqlError::OutOfRange(num)))
     }
 }
 
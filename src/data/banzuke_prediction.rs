@@ -0,0 +1,380 @@
+use itertools::Itertools;
+use rusqlite::{Connection, Result as SqlResult, Transaction};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::basho::top_players;
+use super::leaders::{assign_ord, Rankable};
+use super::{Award, BashoId, DataError, Player, PlayerId, Rank, RankName, Result, RikishiId};
+
+/// The most a single rikishi can add to a prediction's distance, so that one retirement or surprise demotion doesn't sink an otherwise good prediction.
+pub const MAX_DISTANCE: u32 = 20;
+
+/// Predictions for a basho's banzuke open once the previous basho is finalized and close when the real banzuke is imported.
+pub fn is_open(db: &Connection, basho_id: BashoId) -> Result<bool> {
+    Ok(db.query_row(
+        "
+            SELECT
+                EXISTS (SELECT 1 FROM award WHERE basho_id = :prev_basho_id AND type = :award)
+                AND NOT EXISTS (SELECT 1 FROM banzuke WHERE basho_id = :basho_id)
+        ",
+        named_params! {
+            ":basho_id": basho_id,
+            ":prev_basho_id": basho_id.incr(-1),
+            ":award": Award::EmperorsCup,
+        },
+        |row| row.get(0),
+    )?)
+}
+
+/// A rikishi who can be placed on a predicted banzuke: anyone in makuuchi or juryo for the previous basho.
+pub struct Candidate {
+    pub id: RikishiId,
+    pub name: String,
+    pub rank: Rank,
+    pub wins: u8,
+    pub losses: u8,
+}
+
+impl Candidate {
+    /// Candidates in banzuke order for predicting `basho_id`.
+    pub fn for_basho(db: &Connection, basho_id: BashoId) -> Result<Vec<Self>> {
+        let candidates = db
+            .prepare(
+                "
+                SELECT
+                    b.rikishi_id,
+                    b.family_name,
+                    b.rank,
+                    COALESCE(SUM(t.win), 0) AS wins,
+                    COUNT(t.win) - COALESCE(SUM(t.win), 0) AS losses
+                FROM banzuke AS b
+                LEFT JOIN torikumi AS t ON t.basho_id = b.basho_id AND t.rikishi_id = b.rikishi_id
+                WHERE b.basho_id = ?
+                GROUP BY b.rikishi_id
+            ",
+            )?
+            .query_map(params![basho_id.incr(-1)], |row| {
+                Ok(Self {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    rank: row.get(2)?,
+                    wins: row.get(3)?,
+                    losses: row.get(4)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .filter(|c| c.rank.name <= RankName::Juryo)
+            .sorted_by_key(|c| c.rank)
+            .collect();
+        Ok(candidates)
+    }
+}
+
+/// The ranks to fill on a predicted banzuke, which are the previous basho's makuuchi ranks.
+pub fn slots(candidates: &[Candidate]) -> Vec<Rank> {
+    candidates
+        .iter()
+        .map(|c| c.rank)
+        .filter(|rank| rank.is_makuuchi())
+        .collect()
+}
+
+/// Replaces the player's prediction. Every slot must be filled, each with a different candidate.
+pub fn save_prediction(
+    db: &mut Connection,
+    player_id: PlayerId,
+    basho_id: BashoId,
+    prediction: &[(Rank, RikishiId)],
+) -> Result<()> {
+    let txn = db.transaction()?;
+    if !is_open(&txn, basho_id)? {
+        return Err(DataError::PredictionsClosed);
+    }
+    let candidates = Candidate::for_basho(&txn, basho_id)?;
+    let slots: BTreeSet<Rank> = slots(&candidates).into_iter().collect();
+    let candidate_ids: HashSet<RikishiId> = candidates.iter().map(|c| c.id).collect();
+    let ranks: BTreeSet<Rank> = prediction.iter().map(|(rank, _)| *rank).collect();
+    let rikishi: HashSet<RikishiId> = prediction.iter().map(|(_, id)| *id).collect();
+    if ranks != slots || rikishi.len() != prediction.len() || !rikishi.is_subset(&candidate_ids) {
+        return Err(DataError::InvalidPicks);
+    }
+
+    txn.execute(
+        "DELETE FROM banzuke_prediction WHERE player_id = ? AND basho_id = ?",
+        params![player_id, basho_id],
+    )?;
+    let mut stmt = txn.prepare(
+        "
+            INSERT INTO banzuke_prediction (player_id, basho_id, rikishi_id, rank)
+            VALUES (?, ?, ?, ?)
+        ",
+    )?;
+    for (rank, rikishi_id) in prediction {
+        stmt.execute(params![player_id, basho_id, rikishi_id, rank])?;
+    }
+    drop(stmt);
+    txn.commit()?;
+    Ok(())
+}
+
+/// One rikishi on a player's predicted banzuke, alongside where they actually landed once the banzuke is out.
+pub struct PredictedRank {
+    pub rikishi_id: RikishiId,
+    pub name: String,
+    pub predicted: Rank,
+    pub actual: Option<Rank>,
+    pub distance: Option<u32>,
+}
+
+impl PredictedRank {
+    pub fn for_player(
+        db: &Connection,
+        player_id: PlayerId,
+        basho_id: BashoId,
+    ) -> Result<Vec<Self>> {
+        let predicted = db
+            .prepare(
+                "
+                SELECT
+                    bp.rikishi_id,
+                    r.family_name,
+                    bp.rank,
+                    actual.rank AS actual_rank,
+                    bp.distance
+                FROM banzuke_prediction AS bp
+                JOIN rikishi AS r ON r.id = bp.rikishi_id
+                LEFT JOIN banzuke AS actual
+                    ON actual.basho_id = bp.basho_id AND actual.rikishi_id = bp.rikishi_id
+                WHERE bp.player_id = ? AND bp.basho_id = ?
+            ",
+            )?
+            .query_map(params![player_id, basho_id], |row| {
+                Ok(Self {
+                    rikishi_id: row.get(0)?,
+                    name: row.get(1)?,
+                    predicted: row.get(2)?,
+                    actual: row.get(3)?,
+                    distance: row.get(4)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .sorted_by_key(|p| p.predicted)
+            .collect();
+        Ok(predicted)
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.actual == Some(self.predicted)
+    }
+}
+
+/// Scores every prediction for the basho against its imported banzuke and bestows the banzuke prize. A prediction's distance is the number of banzuke slots between the predicted and actual rank, so a rank that doesn't exist on the real banzuke, such as a third sekiwake, still scores sensibly. Safe to run again when the banzuke is corrected.
+pub fn score_predictions(txn: &Transaction, basho_id: BashoId) -> Result<()> {
+    let predictions: Vec<(PlayerId, RikishiId, Rank)> = txn
+        .prepare("SELECT player_id, rikishi_id, rank FROM banzuke_prediction WHERE basho_id = ?")?
+        .query_map(params![basho_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<SqlResult<_>>()?;
+    if predictions.is_empty() {
+        return Ok(());
+    }
+    let actual: HashMap<RikishiId, Rank> = txn
+        .prepare("SELECT rikishi_id, rank FROM banzuke WHERE basho_id = ?")?
+        .query_map(params![basho_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<SqlResult<_>>()?;
+    let ranks: Vec<Rank> = actual.values().copied().sorted().collect();
+    let slot = |rank: Rank| ranks.partition_point(|r| *r < rank);
+
+    let mut stmt = txn.prepare(
+        "
+            UPDATE banzuke_prediction
+            SET distance = ?
+            WHERE player_id = ? AND basho_id = ? AND rikishi_id = ?
+        ",
+    )?;
+    let mut totals: HashMap<PlayerId, u32> = HashMap::new();
+    for (player_id, rikishi_id, rank) in predictions {
+        let distance = actual.get(&rikishi_id).map_or(MAX_DISTANCE, |actual_rank| {
+            (slot(*actual_rank).abs_diff(slot(rank)) as u32).min(MAX_DISTANCE)
+        });
+        stmt.execute(params![distance, player_id, basho_id, rikishi_id])?;
+        *totals.entry(player_id).or_default() += distance;
+    }
+    info!(
+        "scored {} banzuke predictions for {}",
+        totals.len(),
+        basho_id
+    );
+
+    txn.execute(
+        "DELETE FROM award WHERE basho_id = ? AND type = ?",
+        params![basho_id, Award::BanzukePrediction],
+    )?;
+    let winners = top_players(
+        totals
+            .into_iter()
+            .map(|(player_id, total)| (player_id, -(total as i64))),
+        i64::MIN,
+    );
+    for player_id in winners {
+        debug!("  ! awarding banzuke prize to {}", player_id);
+        txn.execute(
+            "INSERT INTO award (basho_id, player_id, type) VALUES (?, ?, ?)",
+            params![basho_id, player_id, Award::BanzukePrediction],
+        )?;
+    }
+    Ok(())
+}
+
+/// A player's place among everyone who predicted a banzuke, lowest total distance first.
+pub struct BanzukePredictionStanding {
+    pub ord: usize,
+    pub player: Player,
+    pub distance: u32,
+    pub exact: u32,
+}
+
+impl Rankable for BanzukePredictionStanding {
+    fn get_score(&self) -> i32 {
+        -(self.distance as i32)
+    }
+
+    fn set_rank(&mut self, ord: usize) {
+        self.ord = ord;
+    }
+}
+
+impl BanzukePredictionStanding {
+    pub fn for_basho(db: &Connection, basho_id: BashoId) -> Result<Vec<Self>> {
+        let mut standings = db
+            .prepare(
+                "
+                SELECT
+                    player.*,
+                    SUM(bp.distance) AS distance,
+                    SUM(bp.rank = actual.rank) AS exact
+                FROM banzuke_prediction AS bp
+                JOIN player_info AS player ON player.id = bp.player_id
+                LEFT JOIN banzuke AS actual
+                    ON actual.basho_id = bp.basho_id AND actual.rikishi_id = bp.rikishi_id
+                WHERE bp.basho_id = ? AND bp.distance IS NOT NULL
+                GROUP BY player.id
+                ORDER BY distance ASC, exact DESC, player.id
+            ",
+            )?
+            .query_map(params![basho_id], |row| {
+                Ok(Self {
+                    ord: 0,
+                    player: Player::from_row(row)?,
+                    distance: row.get("distance")?,
+                    exact: row.get::<_, Option<u32>>("exact")?.unwrap_or(0),
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        assign_ord(&mut standings.iter_mut());
+        Ok(standings)
+    }
+
+    /// How many players have saved a prediction, before any are scored.
+    pub fn entrant_count(db: &Connection, basho_id: BashoId) -> Result<u32> {
+        Ok(db.query_row(
+            "SELECT COUNT(DISTINCT player_id) FROM banzuke_prediction WHERE basho_id = ?",
+            params![basho_id],
+            |row| row.get(0),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::testing;
+    use chrono::Utc;
+    use std::str::FromStr;
+
+    const BASHO: BashoId = BashoId {
+        year: 2026,
+        month: 11,
+    };
+
+    fn predict(db: &Connection, player_id: PlayerId, prediction: &[(RikishiId, &str)]) {
+        for (rikishi_id, rank) in prediction {
+            db.execute(
+                "
+                    INSERT INTO banzuke_prediction (player_id, basho_id, rikishi_id, rank)
+                    VALUES (?, ?, ?, ?)
+                ",
+                params![player_id, BASHO, rikishi_id, Rank::from_str(rank).unwrap()],
+            )
+            .unwrap();
+        }
+    }
+
+    fn distances(db: &Connection, player_id: PlayerId) -> Vec<u32> {
+        db.prepare(
+            "
+                SELECT distance
+                FROM banzuke_prediction
+                WHERE player_id = ? AND basho_id = ?
+                ORDER BY rikishi_id
+            ",
+        )
+        .unwrap()
+        .query_map(params![player_id, BASHO], |row| row.get(0))
+        .unwrap()
+        .collect::<SqlResult<_>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn score_predictions_by_slot_distance() {
+        let mut db = testing::test_db();
+        testing::insert_basho(&db, BASHO.incr(-1), Utc::now());
+        testing::insert_basho(&db, BASHO, Utc::now());
+        // Shodai was in makuuchi last basho but retired before this banzuke.
+        testing::insert_banzuke(&db, BASHO.incr(-1), 5, "Shodai", "M1e");
+        for (id, name, rank) in [
+            (1, "Hoshoryu", "Y1e"),
+            (2, "Onosato", "O1e"),
+            (3, "Kirishima", "S1e"),
+            (4, "Takayasu", "K1e"),
+        ] {
+            testing::insert_banzuke(&db, BASHO, id, name, rank);
+        }
+        for (id, name) in [
+            (1, "exact"),
+            (2, "swapped"),
+            (3, "retiree"),
+            (4, "also-exact"),
+        ] {
+            testing::insert_player(&db, id, name);
+        }
+        let exact = [(1, "Y1e"), (2, "O1e"), (3, "S1e"), (4, "K1e")];
+        predict(&db, 1, &exact);
+        // A second sekiwake slot isn't on the real banzuke, but it falls between S1e and K1e.
+        predict(&db, 2, &[(1, "O1e"), (2, "Y1e"), (3, "S1w"), (4, "M1e")]);
+        predict(&db, 3, &[(1, "Y1e"), (2, "O1e"), (3, "S1e"), (5, "K1e")]);
+        predict(&db, 4, &exact);
+
+        let txn = db.transaction().unwrap();
+        score_predictions(&txn, BASHO).unwrap();
+        txn.commit().unwrap();
+
+        assert_eq!(distances(&db, 1), [0, 0, 0, 0]);
+        assert_eq!(distances(&db, 2), [1, 1, 1, 1]);
+        assert_eq!(distances(&db, 3), [0, 0, 0, MAX_DISTANCE]);
+        let winners: Vec<PlayerId> = db
+            .prepare(
+                "SELECT player_id FROM award WHERE basho_id = ? AND type = ? ORDER BY player_id",
+            )
+            .unwrap()
+            .query_map(params![BASHO, Award::BanzukePrediction], |row| row.get(0))
+            .unwrap()
+            .collect::<SqlResult<_>>()
+            .unwrap();
+        assert_eq!(winners, [1, 4]);
+    }
+}
@@ -3,13 +3,23 @@ use chrono::offset::Utc;
 use chrono::{DateTime, TimeDelta};
 use itertools::Itertools;
 use result::ResultIteratorExt;
-use rusqlite::{params_from_iter, Connection, Result as SqlResult, Transaction};
+use rusqlite::{params_from_iter, Connection, OptionalExtension, Result as SqlResult, Transaction};
 use serde::Deserialize;
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::convert::From;
+use std::fmt;
 
-use super::leaders::HistoricLeader;
+use super::auto_pick;
+use super::banzuke_prediction;
+use super::bout_prediction;
+use super::heya::HeyaStanding;
+use super::heya_draft;
+use super::over_under;
+use super::pick_history::PickVersion;
+use super::promotion;
+use super::rating;
+use super::simulation;
 use super::{
     Award, BashoId, DataError, Day, Player, PlayerId, Rank, RankGroup, RankSide, Result, RikishiId,
 };
@@ -24,10 +34,16 @@ pub struct BashoInfo {
     pub player_count: usize,
     pub winners: Vec<Player>,
     pub winning_score: Option<u8>,
+    /// Whether other players' picks stay hidden until the basho starts.
+    pub sealed_picks: bool,
+    /// The rikishi who won the yusho, once recorded.
+    pub yusho_rikishi_id: Option<RikishiId>,
+    /// Whether each player may mark one pick as captain, whose wins count double.
+    pub captain_picks: bool,
 }
 
 const VERY_FIRST_BASHO: &str = "201901";
-const SAVE_PICKS_GRACE_PERIOD_MINUTES: i64 = 15;
+pub const SAVE_PICKS_GRACE_PERIOD_MINUTES: i64 = 15;
 
 impl BashoInfo {
     /// Returns the current basho id if one is in session; otherwise returns the next basho after that last completed one.
@@ -36,9 +52,9 @@ impl BashoInfo {
             "
             SELECT MAX(id)
             FROM basho AS b
-            WHERE EXISTS (SELECT 1 FROM award AS a WHERE a.basho_id = b.id)
+            WHERE EXISTS (SELECT 1 FROM award AS a WHERE a.basho_id = b.id AND a.type = ?)
         ",
-            [],
+            params![Award::EmperorsCup],
             |row| row.get(0),
         )?;
         Ok(last_completed_basho
@@ -52,6 +68,9 @@ impl BashoInfo {
                 COUNT(*) AS n,
                 basho.start_date,
                 basho.venue,
+                basho.sealed_picks,
+                basho.yusho_rikishi_id,
+                basho.captain_picks,
                 ebr.url AS external_link,
                 CASE
                     WHEN ebr.basho_id IS NULL THEN
@@ -80,6 +99,9 @@ impl BashoInfo {
                                  external_link: row.get("external_link")?,
                                  player_count: row.get::<_, u32>("player_count")? as usize,
                                  winning_score: row.get("winning_score")?,
+                                 sealed_picks: row.get("sealed_picks")?,
+                                 yusho_rikishi_id: row.get("yusho_rikishi_id")?,
+                                 captain_picks: row.get("captain_picks")?,
                                  winners: BashoInfo::fetch_basho_winners(db, id)?,
                              }))
                          }
@@ -93,6 +115,9 @@ impl BashoInfo {
                     basho.id,
                     basho.start_date,
                     basho.venue,
+                    basho.sealed_picks,
+                    basho.yusho_rikishi_id,
+                    basho.captain_picks,
                     ebr.url AS external_link,
                     CASE
                         WHEN ebr.basho_id IS NULL THEN
@@ -120,6 +145,9 @@ impl BashoInfo {
                 external_link: row.get("external_link")?,
                 player_count: row.get::<_, u32>("player_count")? as usize,
                 winning_score: row.get("winning_score")?,
+                sealed_picks: row.get("sealed_picks")?,
+                yusho_rikishi_id: row.get("yusho_rikishi_id")?,
+                captain_picks: row.get("captain_picks")?,
                 winners: BashoInfo::fetch_basho_winners(db, basho_id)?,
             })
         })?;
@@ -144,6 +172,9 @@ impl BashoInfo {
                     basho.id,
                     basho.start_date,
                     basho.venue,
+                    basho.sealed_picks,
+                    basho.yusho_rikishi_id,
+                    basho.captain_picks,
                     ebr.url AS external_link,
                     CASE
                         WHEN ebr.basho_id IS NULL THEN COUNT(DISTINCT br.player_id)
@@ -165,6 +196,9 @@ impl BashoInfo {
                 external_link: row.get("external_link")?,
                 player_count: row.get::<_, u32>("player_count")? as usize,
                 winning_score: row.get("winning_score")?,
+                sealed_picks: row.get("sealed_picks")?,
+                yusho_rikishi_id: row.get("yusho_rikishi_id")?,
+                captain_picks: row.get("captain_picks")?,
                 winners: winners.remove(&basho_id).unwrap_or_default(),
             })
         })?
@@ -176,6 +210,25 @@ impl BashoInfo {
         self.start_date < Utc::now()
     }
 
+    /// Whether other players' picks are currently hidden.
+    pub fn picks_are_sealed(&self) -> bool {
+        self.sealed_picks && !self.has_started()
+    }
+
+    pub fn picks_are_sealed_for_id(db: &Connection, id: BashoId) -> Result<bool> {
+        let (sealed_picks, start_date): (bool, DateTime<Utc>) = db.query_row(
+            "SELECT sealed_picks, start_date FROM basho WHERE id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(sealed_picks && start_date > Utc::now())
+    }
+
+    /// Picks saved after this are rejected.
+    pub fn pick_deadline(&self) -> DateTime<Utc> {
+        self.start_date + TimeDelta::minutes(SAVE_PICKS_GRACE_PERIOD_MINUTES)
+    }
+
     pub fn link_url(&self) -> String {
         if let Some(str) = &self.external_link {
             str.to_owned()
@@ -230,15 +283,17 @@ pub fn save_player_picks(
     player_id: PlayerId,
     basho_id: BashoId,
     picks: [Option<RikishiId>; 5],
+    captain: Option<RikishiId>,
+    yusho_pick: Option<RikishiId>,
 ) -> Result<()> {
     let txn = db.transaction()?;
-    let start_date: DateTime<Utc> = txn.query_row(
+    let (start_date, captain_picks): (DateTime<Utc>, bool) = txn.query_row(
         "
-        SELECT start_date
+        SELECT start_date, captain_picks
         FROM basho
         WHERE id = ?",
         params![basho_id],
-        |row| row.get(0),
+        |row| Ok((row.get(0)?, row.get(1)?)),
     )?;
     let grace_period = TimeDelta::minutes(SAVE_PICKS_GRACE_PERIOD_MINUTES);
     if start_date + grace_period < Utc::now() {
@@ -268,6 +323,11 @@ pub fn save_player_picks(
     {
         return Err(DataError::InvalidPicks);
     }
+    if let Some(captain) = captain {
+        if !captain_picks || !picks.contains(&Some(captain)) {
+            return Err(DataError::InvalidPicks);
+        }
+    }
 
     txn.execute(
         "
@@ -282,7 +342,37 @@ pub fn save_player_picks(
         );
         txn.execute(
             "
-            INSERT INTO pick (player_id, basho_id, rikishi_id)
+            INSERT INTO pick (player_id, basho_id, rikishi_id, is_captain)
+            VALUES (?, ?, ?, ?)",
+            params![
+                player_id,
+                basho_id,
+                rikishi_id,
+                captain == Some(*rikishi_id)
+            ],
+        )?;
+    }
+    PickVersion::record(&txn, player_id, basho_id, false)?;
+
+    txn.execute(
+        "
+        DELETE FROM yusho_pick
+        WHERE player_id = ? AND basho_id = ?",
+        params![player_id, basho_id],
+    )?;
+    if let Some(rikishi_id) = yusho_pick {
+        let on_banzuke: bool = txn.query_row(
+            "
+            SELECT EXISTS (SELECT 1 FROM banzuke WHERE basho_id = ? AND rikishi_id = ?)",
+            params![basho_id, rikishi_id],
+            |row| row.get(0),
+        )?;
+        if !on_banzuke {
+            return Err(DataError::InvalidPicks);
+        }
+        txn.execute(
+            "
+            INSERT INTO yusho_pick (player_id, basho_id, rikishi_id)
             VALUES (?, ?, ?)",
             params![player_id, basho_id, rikishi_id],
         )?;
@@ -292,23 +382,223 @@ pub fn save_player_picks(
     Ok(())
 }
 
+/// The pick the player marked as captain for a basho.
+pub fn captain_pick_for_player(
+    db: &Connection,
+    player_id: PlayerId,
+    basho_id: BashoId,
+) -> Result<Option<RikishiId>> {
+    Ok(db
+        .query_row(
+            "SELECT rikishi_id FROM pick WHERE player_id = ? AND basho_id = ? AND is_captain",
+            params![player_id, basho_id],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// The player's prediction of the yusho winner for a basho.
+pub fn yusho_pick_for_player(
+    db: &Connection,
+    player_id: PlayerId,
+    basho_id: BashoId,
+) -> Result<Option<RikishiId>> {
+    Ok(db
+        .query_row(
+            "SELECT rikishi_id FROM yusho_pick WHERE player_id = ? AND basho_id = ?",
+            params![player_id, basho_id],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Makes an earlier version of a player's picks current again, for an admin settling a dispute. This bypasses the deadline and is recorded as a new version.
+pub fn restore_player_picks(
+    db: &mut Connection,
+    player_id: PlayerId,
+    basho_id: BashoId,
+    version: u32,
+) -> Result<()> {
+    info!(
+        "restoring player {} picks for {} to version {}",
+        player_id, basho_id, version
+    );
+    let txn = db.transaction()?;
+    let rikishi: Vec<(RikishiId, bool, bool)> = txn
+        .prepare(
+            "
+            SELECT vr.rikishi_id, vr.is_auto, vr.is_captain
+            FROM pick_version AS v
+            JOIN pick_version_rikishi AS vr ON vr.pick_version_id = v.id
+            WHERE v.player_id = ? AND v.basho_id = ? AND v.version = ?
+        ",
+        )?
+        .query_map(params![player_id, basho_id, version], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<SqlResult<_>>()?;
+    if rikishi.is_empty() {
+        return Err(DataError::InvalidPicks);
+    }
+    txn.execute(
+        "DELETE FROM pick WHERE player_id = ? AND basho_id = ?",
+        params![player_id, basho_id],
+    )?;
+    for (rikishi_id, is_auto, is_captain) in rikishi {
+        txn.execute(
+            "
+            INSERT INTO pick (player_id, basho_id, rikishi_id, is_auto, is_captain)
+            VALUES (?, ?, ?, ?, ?)",
+            params![player_id, basho_id, rikishi_id, is_auto, is_captain],
+        )?;
+    }
+    PickVersion::record(&txn, player_id, basho_id, false)?;
+    upsert_basho_results(&txn, basho_id, false)?;
+    txn.commit()?;
+    Ok(())
+}
+
+/// Why one of a player's previous picks couldn't be carried over by `LastPicks::for_player`.
+pub enum PickConflict {
+    /// Both rikishi are now in the same group; the higher ranked one is kept.
+    SameGroup {
+        kept: String,
+        dropped: String,
+        group: RankGroup,
+    },
+    /// Retired, or no longer in a pickable rank group.
+    OffBanzuke {
+        name: String,
+    },
+    Kyujyo {
+        name: String,
+    },
+}
+
+impl fmt::Display for PickConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PickConflict::SameGroup {
+                kept,
+                dropped,
+                group,
+            } => write!(
+                f,
+                "{kept} and {dropped} are both {} now; kept {kept}",
+                group.label()
+            ),
+            PickConflict::OffBanzuke { name } => {
+                write!(f, "{name} is no longer in a pickable rank")
+            }
+            PickConflict::Kyujyo { name } => write!(f, "{name} is kyujyo this basho"),
+        }
+    }
+}
+
+/// A player's picks from the last basho they entered, slotted into their rank groups on a new banzuke.
+pub struct LastPicks {
+    pub basho_id: BashoId,
+    pub picks: [Option<RikishiId>; 5],
+    pub conflicts: Vec<PickConflict>,
+}
+
+impl LastPicks {
+    pub fn for_player(
+        db: &Connection,
+        player_id: PlayerId,
+        basho_id: BashoId,
+    ) -> Result<Option<Self>> {
+        let rows: Vec<(BashoId, RikishiId, String, Option<Rank>, bool)> = db
+            .prepare(
+                "
+                SELECT
+                    pick.basho_id,
+                    pick.rikishi_id,
+                    prev.family_name,
+                    cur.rank,
+                    COALESCE(cur.kyujyo, 0)
+                FROM pick
+                JOIN banzuke AS prev ON prev.rikishi_id = pick.rikishi_id AND prev.basho_id = pick.basho_id
+                LEFT JOIN banzuke AS cur ON cur.rikishi_id = pick.rikishi_id AND cur.basho_id = :basho_id
+                WHERE
+                    pick.player_id = :player_id
+                    AND pick.basho_id = (
+                        SELECT MAX(basho_id)
+                        FROM pick
+                        WHERE player_id = :player_id AND basho_id < :basho_id
+                    )
+            ",
+            )?
+            .query_map(
+                named_params! {":basho_id": basho_id, ":player_id": player_id},
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )?
+            .collect::<SqlResult<_>>()?;
+        let Some(last_basho_id) = rows.first().map(|row| row.0) else {
+            return Ok(None);
+        };
+
+        let mut picks: [Option<RikishiId>; 5] = [None; 5];
+        let mut kept_names: [Option<String>; 5] = Default::default();
+        let mut conflicts = vec![];
+        // Highest rank first so that it wins any group conflict.
+        for (_, rikishi_id, name, rank, is_kyujyo) in rows
+            .into_iter()
+            .sorted_by_key(|row| (row.3.is_none(), row.3))
+        {
+            let Some(group) = rank
+                .map(|r| r.group())
+                .filter(|g| g.as_index() < RankGroup::count())
+            else {
+                conflicts.push(PickConflict::OffBanzuke { name });
+                continue;
+            };
+            if is_kyujyo {
+                conflicts.push(PickConflict::Kyujyo { name });
+            } else if let Some(kept) = &kept_names[group.as_index()] {
+                conflicts.push(PickConflict::SameGroup {
+                    kept: kept.to_owned(),
+                    dropped: name,
+                    group,
+                });
+            } else {
+                picks[group.as_index()] = Some(rikishi_id);
+                kept_names[group.as_index()] = Some(name);
+            }
+        }
+        Ok(Some(Self {
+            basho_id: last_basho_id,
+            picks,
+            conflicts,
+        }))
+    }
+
+    pub fn rikishi_ids(&self) -> String {
+        self.picks.iter().flatten().join(",")
+    }
+}
+
 pub fn update_basho(
     db: &mut Connection,
     basho_id: BashoId,
     venue: &str,
     start_date: &NaiveDateTime,
+    sealed_picks: bool,
+    captain_picks: bool,
     banzuke: &[(String, Rank, bool)],
 ) -> Result<()> {
     let txn = db.transaction()?;
     txn.execute(
         "
-        INSERT INTO basho (id, start_date, venue)
-        VALUES (?, ?, ?)
+        INSERT INTO basho (id, start_date, venue, sealed_picks, captain_picks)
+        VALUES (?, ?, ?, ?, ?)
         ON CONFLICT (id) DO UPDATE SET
             start_date = excluded.start_date,
-            venue = excluded.venue
+            venue = excluded.venue,
+            sealed_picks = excluded.sealed_picks,
+            captain_picks = excluded.captain_picks
         ",
-        params![basho_id, start_date, venue],
+        params![basho_id, start_date, venue, sealed_picks, captain_picks],
     )?;
 
     let mut rikishi_ids = HashMap::new();
@@ -384,6 +674,9 @@ pub fn update_basho(
             ],
         )?;
     }
+    if !banzuke.is_empty() {
+        banzuke_prediction::score_predictions(&txn, basho_id)?;
+    }
     txn.commit()?;
 
     Ok(())
@@ -405,6 +698,65 @@ pub fn update_torikumi(
 
     let txn = db.transaction()?;
 
+    let (rikishi_ids, rikishi_ranks) = banzuke_name_lookup(&txn, basho_id)?;
+
+    txn.execute(
+        "
+            DELETE FROM torikumi
+            WHERE basho_id = ? AND day = ?
+        ",
+        params![basho_id, day],
+    )?;
+
+    for (seq, TorikumiMatchUpdateData { winner, loser }) in torikumi.iter().enumerate() {
+        let winner_id = rikishi_ids
+            .get(winner)
+            .ok_or_else(|| DataError::RikishiNotFound {
+                family_name: winner.to_owned(),
+            })?;
+        let loser_id = rikishi_ids
+            .get(loser)
+            .ok_or_else(|| DataError::RikishiNotFound {
+                family_name: loser.to_owned(),
+            })?;
+        let winner_rank = rikishi_ranks.get(winner_id).unwrap();
+        let loser_rank = rikishi_ranks.get(loser_id).unwrap();
+
+        let insert_1 = |side, rikishi_id, win| {
+            txn.execute(
+                "
+                    INSERT INTO torikumi (basho_id, day, seq, side, rikishi_id, win)
+                    VALUES (?, ?, ?, ?, ?, ?)
+                ",
+                params![basho_id, day, seq as u32, side, rikishi_id, win],
+            )
+        };
+
+        insert_1(bout_side(winner_rank, loser_rank), winner_id, true)?;
+        insert_1(bout_side(loser_rank, winner_rank), loser_id, false)?;
+    }
+
+    if day == 1 {
+        // The pick deadline has passed by the time the first results arrive.
+        let added = auto_pick::fill_auto_picks(&txn, basho_id)?;
+        debug!("added {} auto-picks for {}", added, basho_id);
+        let drafted = heya_draft::HeyaDraft::finish_all(&txn, basho_id)?;
+        debug!("auto-drafted {} rikishi for {}", drafted, basho_id);
+    }
+    upsert_basho_results(&txn, basho_id, false)?;
+    simulation::simulate_basho(&txn, basho_id, day)?;
+    bout_prediction::score_day(&txn, basho_id, day)?;
+
+    txn.commit()?;
+
+    Ok(())
+}
+
+/// Maps each shikona on the basho's banzuke to its rikishi id, and each id to its rank.
+fn banzuke_name_lookup(
+    txn: &Transaction,
+    basho_id: BashoId,
+) -> Result<(HashMap<String, i64>, HashMap<i64, Rank>)> {
     let mut rikishi_ids = HashMap::new();
     let mut rikishi_ranks = HashMap::new();
     let mut ambiguous_shikona = Vec::<String>::new();
@@ -436,6 +788,51 @@ pub fn update_torikumi(
         });
     }
 
+    Ok((rikishi_ids, rikishi_ranks))
+}
+
+/// Figuring out the side: the rikishi with the higher rank appear on their own rank.side
+fn bout_side(rank: &Rank, opponent_rank: &Rank) -> RankSide {
+    if rank > opponent_rank {
+        rank.side
+    } else {
+        opponent_rank.side.other()
+    }
+}
+
+/// Stores the announced bouts for a day before they are fought, with no winner yet, so they can be predicted. Does nothing once the day has any results.
+pub fn schedule_torikumi(
+    db: &mut Connection,
+    basho_id: BashoId,
+    day: Day,
+    bouts: &[(String, String)],
+) -> Result<usize> {
+    debug!(
+        "scheduling {} bouts for {} day {}",
+        bouts.len(),
+        basho_id,
+        day
+    );
+
+    let txn = db.transaction()?;
+    let has_results: bool = txn.query_row(
+        "
+            SELECT EXISTS (
+                SELECT 1 FROM torikumi WHERE basho_id = ? AND day = ? AND win IS NOT NULL
+            )
+        ",
+        params![basho_id, day],
+        |row| row.get(0),
+    )?;
+    if has_results {
+        warn!(
+            "not scheduling {} day {}: results already in",
+            basho_id, day
+        );
+        return Ok(0);
+    }
+
+    let (rikishi_ids, rikishi_ranks) = banzuke_name_lookup(&txn, basho_id)?;
     txn.execute(
         "
             DELETE FROM torikumi
@@ -443,57 +840,43 @@ pub fn update_torikumi(
         ",
         params![basho_id, day],
     )?;
-
-    for (seq, TorikumiMatchUpdateData { winner, loser }) in torikumi.iter().enumerate() {
-        let winner_id = rikishi_ids
-            .get(winner)
+    let mut insert = txn.prepare(
+        "
+            INSERT INTO torikumi (basho_id, day, seq, side, rikishi_id, win)
+            VALUES (?, ?, ?, ?, ?, NULL)
+        ",
+    )?;
+    for (seq, (a, b)) in bouts.iter().enumerate() {
+        let a_id = rikishi_ids
+            .get(a)
             .ok_or_else(|| DataError::RikishiNotFound {
-                family_name: winner.to_owned(),
+                family_name: a.to_owned(),
             })?;
-        let loser_id = rikishi_ids
-            .get(loser)
+        let b_id = rikishi_ids
+            .get(b)
             .ok_or_else(|| DataError::RikishiNotFound {
-                family_name: loser.to_owned(),
+                family_name: b.to_owned(),
             })?;
-        let winner_rank = rikishi_ranks.get(winner_id).unwrap();
-        let loser_rank = rikishi_ranks.get(loser_id).unwrap();
-
-        let insert_1 = |side, rikishi_id, win| {
-            txn.execute(
-                "
-                    INSERT INTO torikumi (basho_id, day, seq, side, rikishi_id, win)
-                    VALUES (?, ?, ?, ?, ?, ?)
-                ",
-                params![basho_id, day, seq as u32, side, rikishi_id, win],
-            )
-        };
-
-        // Figuring out the side: the rikishi with the higher rank appear on their own rank.side
-        insert_1(
-            if winner_rank > loser_rank {
-                winner_rank.side
-            } else {
-                loser_rank.side.other()
-            },
-            winner_id,
-            true,
-        )?;
-        insert_1(
-            if loser_rank > winner_rank {
-                loser_rank.side
-            } else {
-                winner_rank.side.other()
-            },
-            loser_id,
-            false,
-        )?;
+        let a_rank = rikishi_ranks.get(a_id).unwrap();
+        let b_rank = rikishi_ranks.get(b_id).unwrap();
+        insert.execute(params![
+            basho_id,
+            day,
+            seq as u32,
+            bout_side(a_rank, b_rank),
+            a_id
+        ])?;
+        insert.execute(params![
+            basho_id,
+            day,
+            seq as u32,
+            bout_side(b_rank, a_rank),
+            b_id
+        ])?;
     }
-
-    upsert_basho_results(&txn, basho_id, false)?;
-
+    drop(insert);
     txn.commit()?;
-
-    Ok(())
+    Ok(bouts.len())
 }
 
 #[derive(Clone)]
@@ -519,6 +902,20 @@ impl BashoRikishi {
         1
     }
 
+    /// Days left to fight, or none if kyujyo.
+    pub fn remaining_days(&self) -> u8 {
+        if self.is_kyujyo {
+            0
+        } else {
+            16 - self.next_day()
+        }
+    }
+
+    /// Wins so far plus every remaining day.
+    pub fn max_wins(&self) -> u8 {
+        self.wins + self.remaining_days()
+    }
+
     pub fn result_chunks(&self) -> Vec<&[Option<bool>]> {
         self.results.chunks(5).collect()
     }
@@ -555,6 +952,18 @@ pub struct FetchBashoRikishi {
 }
 
 impl FetchBashoRikishi {
+    /// Blanks the per-rikishi pick counts, for a basho whose picks are sealed.
+    pub fn hide_pick_counts(&mut self) {
+        for r in self
+            .by_rank
+            .iter_mut()
+            .flat_map(|rr| rr.east.iter_mut().chain(rr.west.iter_mut()))
+            .chain(self.by_id.values_mut())
+        {
+            r.picks = 0;
+        }
+    }
+
     pub fn with_db(db: &Connection, basho_id: BashoId, picks: &HashSet<RikishiId>) -> Result<Self> {
         debug!("fetching rikishi results for basho {}", basho_id);
         struct FetchedRikishiRow(
@@ -682,13 +1091,82 @@ impl FetchBashoRikishi {
 pub fn finalize_basho(db: &mut Connection, basho_id: BashoId) -> Result<()> {
     debug!("finalizing basho {}", basho_id);
     let txn = db.transaction()?;
-    upsert_basho_results(&txn, basho_id, true)?;
-    upsert_player_ranks(&txn, basho_id)?;
+    // A playoff isn't in torikumi, so a tie for the most wins is left for an admin to record.
+    txn.execute(
+        "
+            UPDATE basho
+            SET yusho_rikishi_id = (
+                SELECT rikishi_id
+                FROM (
+                    SELECT rikishi_id, SUM(win) AS wins
+                    FROM torikumi
+                    WHERE basho_id = :basho_id
+                    GROUP BY rikishi_id
+                    ORDER BY wins DESC
+                    LIMIT 2
+                )
+                GROUP BY wins
+                HAVING COUNT(*) = 1
+                ORDER BY wins DESC
+                LIMIT 1
+            )
+            WHERE id = :basho_id AND yusho_rikishi_id IS NULL
+        ",
+        named_params! {":basho_id": basho_id},
+    )?;
+    upsert_final_results(&txn, basho_id)?;
     debug!("committing");
     txn.commit()?;
     Ok(())
 }
 
+/// Everything that follows from a basho's final scores. Finalizing runs this, and so does anything that changes the scores of a finalized basho.
+fn upsert_final_results(txn: &Transaction, basho_id: BashoId) -> Result<()> {
+    upsert_basho_results(txn, basho_id, true)?;
+    upsert_player_ranks(txn, basho_id)?;
+    HeyaStanding::upsert_final(txn, basho_id)?;
+    rating::update_ratings(txn, basho_id)?;
+    over_under::resolve(txn, basho_id)?;
+    Ok(())
+}
+
+/// Records the yusho winner, which resolves everyone's yusho picks. Results are recomputed, and if the basho has already been finalized so is everything that follows from them: awards, the next banzuke, heya standings and ratings.
+pub fn record_yusho_winner(
+    db: &mut Connection,
+    basho_id: BashoId,
+    rikishi_id: RikishiId,
+) -> Result<()> {
+    info!("recording yusho winner {} for {}", rikishi_id, basho_id);
+    let txn = db.transaction()?;
+    let on_banzuke: bool = txn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM banzuke WHERE basho_id = ? AND rikishi_id = ?)",
+        params![basho_id, rikishi_id],
+        |row| row.get(0),
+    )?;
+    if !on_banzuke {
+        return Err(DataError::RikishiNotFound {
+            family_name: rikishi_id.to_string(),
+        });
+    }
+    txn.execute(
+        "UPDATE basho SET yusho_rikishi_id = ? WHERE id = ?",
+        params![rikishi_id, basho_id],
+    )?;
+    // Other awards can be bestowed by hand before the basho ends, but the Emperor's Cup only comes from finalizing.
+    let is_finalized: bool = txn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM award WHERE basho_id = ? AND type = ?)",
+        params![basho_id, Award::EmperorsCup],
+        |row| row.get(0),
+    )?;
+    if is_finalized {
+        upsert_final_results(&txn, basho_id)?;
+    } else {
+        upsert_basho_results(&txn, basho_id, false)?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
 pub fn backfill_past_player_ranks(db: &mut Connection, to_basho: BashoId) -> Result<()> {
     let first_basho = VERY_FIRST_BASHO.parse().unwrap();
     info!(
@@ -713,19 +1191,7 @@ fn upsert_basho_results(txn: &Transaction, basho_id: BashoId, bestow_awards: boo
     );
     let scores = BashoPlayerScore::fetch(txn, basho_id)?;
 
-    if bestow_awards {
-        let count = txn
-            .prepare(
-                "
-                DELETE FROM award
-                WHERE basho_id = ? AND type = ?
-            ",
-            )?
-            .execute(params![basho_id, Award::EmperorsCup])?;
-        debug!("deleted {} previously bestowed emperor's cups", count);
-    }
-
-    // For each player, upsert basho_result and award emperor's cup if they ranked #1
+    // For each player, upsert basho_result
     let mut insert_result_stmt = txn.prepare(
         "
             INSERT INTO basho_result (basho_id, player_id, wins, rank) VALUES (?, ?, ?, ?)
@@ -734,13 +1200,7 @@ fn upsert_basho_results(txn: &Transaction, basho_id: BashoId, bestow_awards: boo
                 rank = excluded.rank
         ",
     )?;
-    let mut insert_award_stmt = txn.prepare(
-        "
-            INSERT INTO award (basho_id, player_id, type)
-            VALUES (?, ?, ?)
-        ",
-    )?;
-    for p in scores {
+    for p in &scores {
         trace!(
             "- rank {} player {} ({}) with {} wins",
             p.rank,
@@ -749,44 +1209,224 @@ fn upsert_basho_results(txn: &Transaction, basho_id: BashoId, bestow_awards: boo
             p.wins
         );
         insert_result_stmt.execute(params![basho_id, p.id, p.wins, p.rank as u32])?;
-        if bestow_awards && p.rank == 1 {
-            debug!("  ! awarding emperor's cup to {}", p.name);
-            insert_award_stmt.execute(params![basho_id, p.id, Award::EmperorsCup])?;
+    }
+
+    if bestow_awards {
+        // Only the computed awards are replaced, so ones bestowed by an admin or decided elsewhere, like the banzuke prize, survive finalizing again.
+        let winners = award_winners(txn, basho_id, &scores)?;
+        let mut delete_award_stmt = txn.prepare(
+            "
+                DELETE FROM award
+                WHERE basho_id = ? AND type = ? AND NOT is_manual
+            ",
+        )?;
+        let mut count = 0;
+        for (award, _) in &winners {
+            count += delete_award_stmt.execute(params![basho_id, award])?;
+        }
+        debug!("deleted {} previously bestowed awards", count);
+
+        let mut insert_award_stmt = txn.prepare(
+            "
+                INSERT INTO award (basho_id, player_id, type)
+                VALUES (?, ?, ?)
+                ON CONFLICT DO NOTHING
+            ",
+        )?;
+        for (award, player_ids) in winners {
+            for player_id in player_ids {
+                debug!("  ! awarding {:?} to {}", award, player_id);
+                insert_award_stmt.execute(params![basho_id, player_id, award])?;
+            }
         }
     }
     Ok(())
 }
 
+/// Decides who receives each `Award` based on the final scores of a basho. Ties are shared, and an award is skipped entirely if nobody qualifies.
+fn award_winners(
+    txn: &Transaction,
+    basho_id: BashoId,
+    scores: &[BashoPlayerScore],
+) -> Result<Vec<(Award, Vec<PlayerId>)>> {
+    let mut awards = vec![(
+        Award::EmperorsCup,
+        scores
+            .iter()
+            .filter(|p| p.rank == 1)
+            .map(|p| p.id)
+            .collect(),
+    )];
+
+    let runner_up_rank = scores.iter().map(|p| p.rank).filter(|r| *r > 1).min();
+    awards.push((
+        Award::JunYusho,
+        scores
+            .iter()
+            .filter(|p| Some(p.rank) == runner_up_rank)
+            .map(|p| p.id)
+            .collect(),
+    ));
+
+    // Outstanding Performance: compare each ranked player's finishing place with their seed among this basho's entrants by player_rank.
+    let prior_ranks: HashMap<PlayerId, Rank> = txn
+        .prepare(
+            "
+            SELECT player_id, rank
+            FROM player_rank
+            WHERE before_basho_id = ?
+        ",
+        )?
+        .query_map(params![basho_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<SqlResult<_>>()?;
+    let seeded_ranks: Vec<Rank> = scores
+        .iter()
+        .filter_map(|p| prior_ranks.get(&p.id).copied())
+        .sorted()
+        .collect();
+    awards.push((
+        Award::OutstandingPerformance,
+        top_players(
+            scores.iter().filter_map(|p| {
+                prior_ranks.get(&p.id).map(|rank| {
+                    let seed = seeded_ranks.partition_point(|r| r < rank) + 1;
+                    (p.id, seed as i64 - p.rank as i64)
+                })
+            }),
+            1,
+        ),
+    ));
+
+    // Technique: each win is weighted by the fraction of entrants who did *not* pick that rikishi.
+    let entrants = scores.len().max(1) as f64;
+    awards.push((
+        Award::Technique,
+        top_players(
+            txn.prepare(
+                "
+                SELECT
+                    p.player_id,
+                    SUM(w.wins * (1.0 - CAST(c.picks AS REAL) / :entrants)) AS technique
+                FROM pick AS p
+                JOIN (
+                    SELECT rikishi_id, COALESCE(SUM(win), 0) AS wins
+                    FROM torikumi
+                    WHERE basho_id = :basho_id
+                    GROUP BY rikishi_id
+                ) AS w ON w.rikishi_id = p.rikishi_id
+                JOIN (
+                    SELECT rikishi_id, COUNT(*) AS picks
+                    FROM pick
+                    WHERE basho_id = :basho_id
+                    GROUP BY rikishi_id
+                ) AS c ON c.rikishi_id = p.rikishi_id
+                WHERE p.basho_id = :basho_id
+                GROUP BY p.player_id
+            ",
+            )?
+            .query_map(
+                named_params! {
+                    ":basho_id": basho_id,
+                    ":entrants": entrants,
+                },
+                |row| Ok((row.get::<_, PlayerId>(0)?, row.get::<_, f64>(1)?)),
+            )?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter(),
+            f64::MIN_POSITIVE,
+        ),
+    ));
+
+    // Fighting Spirit: players with no previous results, including the pre-site spreadsheet era.
+    let first_timers: HashSet<PlayerId> = txn
+        .prepare(
+            "
+            SELECT p.id
+            FROM player AS p
+            WHERE
+                NOT EXISTS (
+                    SELECT 1 FROM basho_result AS br
+                    WHERE br.player_id = p.id AND br.basho_id < :basho_id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM external_basho_player AS e
+                    WHERE e.name = p.name AND e.basho_id < :basho_id
+                )
+        ",
+        )?
+        .query_map(named_params! {":basho_id": basho_id}, |row| row.get(0))?
+        .collect::<SqlResult<_>>()?;
+    awards.push((
+        Award::FightingSpirit,
+        top_players(
+            scores
+                .iter()
+                .filter(|p| first_timers.contains(&p.id))
+                .map(|p| (p.id, p.wins)),
+            1,
+        ),
+    ));
+
+    Ok(awards)
+}
+
+/// Returns all players tied for the highest score, provided that score is at least `min`.
+pub(super) fn top_players<S: PartialOrd + Copy>(
+    candidates: impl Iterator<Item = (PlayerId, S)>,
+    min: S,
+) -> Vec<PlayerId> {
+    let mut best: Option<S> = None;
+    let mut winners = vec![];
+    for (player_id, score) in candidates {
+        if score < min {
+            continue;
+        }
+        match best {
+            Some(b) if score < b => (),
+            Some(b) if score == b => winners.push(player_id),
+            _ => {
+                best = Some(score);
+                winners.clear();
+                winners.push(player_id);
+            }
+        }
+    }
+    winners
+}
+
 fn upsert_player_ranks(txn: &Transaction, last_basho: BashoId) -> Result<()> {
-    let basho_range = last_basho.next().range_for_banzuke();
-    let leaders = HistoricLeader::with_basho_range(txn, &basho_range, u32::MAX)?;
+    let ranks = promotion::next_banzuke(txn, last_basho)?;
     info!(
         "upsert_player_ranks for {} players after basho {}",
-        leaders.len(),
+        ranks.len(),
         last_basho
     );
     let mut insert_rank_stmt = txn.prepare(
         "
-            INSERT INTO player_rank (player_id, before_basho_id, rank, past_year_wins)
-            VALUES (?, ?, ?, ?)
+            INSERT INTO player_rank (player_id, before_basho_id, rank, past_year_wins, reason, is_kadoban)
+            VALUES (?, ?, ?, ?, ?, ?)
             ON CONFLICT (player_id, before_basho_id) DO UPDATE
             SET rank = excluded.rank,
-                past_year_wins = excluded.past_year_wins
+                past_year_wins = excluded.past_year_wins,
+                reason = excluded.reason,
+                is_kadoban = excluded.is_kadoban
         ",
     )?;
-    for l in leaders {
+    for r in ranks {
         trace!(
-            "- player {} ({}) ranked {} with {} wins",
-            l.player.id,
-            l.player.name,
-            l.rank,
-            l.wins.total.unwrap_or(0)
+            "- player {} ranked {} ({:?}) with {} wins",
+            r.player_id,
+            r.rank,
+            r.reason,
+            r.past_year_wins
         );
         insert_rank_stmt.execute(params![
-            l.player.id,
-            basho_range.end,
-            l.rank,
-            l.wins.total.unwrap_or(0)
+            r.player_id,
+            last_basho.next(),
+            r.rank,
+            r.past_year_wins,
+            r.reason,
+            r.is_kadoban
         ])?;
     }
     Ok(())
@@ -796,12 +1436,14 @@ struct BashoPlayerScore {
     id: PlayerId,
     name: String,
     wins: u8,
+    /// Whether the player's yusho pick matches the recorded winner, which breaks ties on wins.
+    yusho_correct: bool,
     rank: usize,
 }
 
 impl Rankable for BashoPlayerScore {
     fn get_score(&self) -> i32 {
-        self.wins as i32
+        self.wins as i32 * 2 + self.yusho_correct as i32
     }
 
     fn set_rank(&mut self, ord: usize) {
@@ -817,11 +1459,14 @@ impl BashoPlayerScore {
                 SELECT
                     p.id,
                     p.name,
-                    bs.wins
+                    bs.wins,
+                    COALESCE(yp.rikishi_id = b.yusho_rikishi_id, 0) AS yusho_correct
                 FROM basho_score AS bs
                 JOIN player AS p ON p.id = bs.player_id
+                JOIN basho AS b ON b.id = bs.basho_id
+                LEFT JOIN yusho_pick AS yp ON yp.player_id = bs.player_id AND yp.basho_id = bs.basho_id
                 WHERE bs.basho_id = ?
-                ORDER BY bs.wins DESC
+                ORDER BY bs.wins DESC, yusho_correct DESC
             ",
             )?
             .query_map(params![basho_id], |row| -> SqlResult<Self> {
@@ -829,6 +1474,7 @@ impl BashoPlayerScore {
                     id: row.get("id")?,
                     name: row.get("name")?,
                     wins: row.get("wins")?,
+                    yusho_correct: row.get("yusho_correct")?,
                     rank: 0,
                 })
             })?
@@ -837,3 +1483,151 @@ impl BashoPlayerScore {
         Ok(players)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::testing;
+
+    fn picks(db: &Connection) -> Vec<(RikishiId, bool, bool)> {
+        db.prepare("SELECT rikishi_id, is_auto, is_captain FROM pick ORDER BY rikishi_id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<SqlResult<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn refinalizing_keeps_awards_bestowed_by_hand() {
+        let mut db = testing::test_db();
+        let basho_id = BashoId::from(202611);
+        testing::insert_basho(&db, basho_id, Utc::now() - TimeDelta::days(15));
+        testing::insert_banzuke(&db, basho_id, 10, "Hoshoryu", "Y1e");
+        testing::insert_banzuke(&db, basho_id, 11, "Kotozakura", "O1e");
+        for day in 1..=15 {
+            db.execute(
+                "
+                    INSERT INTO torikumi (basho_id, day, seq, side, rikishi_id, win)
+                    VALUES (?, ?, 1, ?, 10, ?)
+                ",
+                params![basho_id, day, RankSide::East, day <= 12],
+            )
+            .unwrap();
+        }
+        for (player_id, name, rikishi_id) in [(1, "kisenosato", 10), (2, "hakuho", 11)] {
+            testing::insert_player(&db, player_id, name);
+            db.execute(
+                "INSERT INTO pick (player_id, basho_id, rikishi_id) VALUES (?, ?, ?)",
+                params![player_id, basho_id, rikishi_id],
+            )
+            .unwrap();
+        }
+        // Both players are in their first basho, so the computed Fighting Spirit Prize goes to player 1.
+        Award::FightingSpirit.bestow(&mut db, basho_id, 2).unwrap();
+        let finalize = |db: &mut Connection| {
+            let txn = db.transaction().unwrap();
+            upsert_basho_results(&txn, basho_id, true).unwrap();
+            txn.commit().unwrap();
+        };
+        finalize(&mut db);
+        finalize(&mut db);
+
+        let awards: Vec<(PlayerId, Award)> = db
+            .prepare(
+                "SELECT player_id, type FROM award WHERE basho_id = ? ORDER BY player_id, type",
+            )
+            .unwrap()
+            .query_map(params![basho_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<SqlResult<_>>()
+            .unwrap();
+        assert!(awards.contains(&(1, Award::EmperorsCup)));
+        assert!(awards.contains(&(2, Award::FightingSpirit)));
+    }
+
+    #[test]
+    fn restoring_a_version_brings_back_its_captain_and_auto_picks() {
+        let mut db = testing::test_db();
+        let basho_id = BashoId::from(202611);
+        testing::insert_basho(&db, basho_id, Utc::now() + TimeDelta::days(1));
+        testing::insert_player(&db, 1, "kisenosato");
+        testing::insert_banzuke(&db, basho_id, 10, "Hoshoryu", "Y1e");
+        testing::insert_banzuke(&db, basho_id, 11, "Kotozakura", "O1e");
+        let mut save = |picks: &[(RikishiId, bool, bool)]| {
+            let txn = db.transaction().unwrap();
+            txn.execute("DELETE FROM pick", []).unwrap();
+            for (rikishi_id, is_auto, is_captain) in picks {
+                txn.execute(
+                    "
+                        INSERT INTO pick (player_id, basho_id, rikishi_id, is_auto, is_captain)
+                        VALUES (1, ?, ?, ?, ?)
+                    ",
+                    params![basho_id, rikishi_id, is_auto, is_captain],
+                )
+                .unwrap();
+            }
+            PickVersion::record(&txn, 1, basho_id, false).unwrap();
+            txn.commit().unwrap();
+        };
+        let first = [(10, false, true), (11, true, false)];
+        save(&first);
+        save(&[(10, false, false)]);
+
+        restore_player_picks(&mut db, 1, basho_id, 1).unwrap();
+        assert_eq!(picks(&db), first);
+        let versions = PickVersion::for_player(&db, 1, basho_id).unwrap();
+        assert_eq!(versions[0].version, 3);
+        assert!(versions[0]
+            .rikishi
+            .iter()
+            .any(|r| r.id == 10 && r.is_captain && !r.is_auto));
+    }
+
+    fn captain_basho(captain_picks: bool) -> (Connection, BashoId) {
+        let db = testing::test_db();
+        let basho_id = BashoId::from(202611);
+        testing::insert_basho(&db, basho_id, Utc::now() + TimeDelta::days(1));
+        db.execute(
+            "UPDATE basho SET captain_picks = ? WHERE id = ?",
+            params![captain_picks, basho_id],
+        )
+        .unwrap();
+        testing::insert_player(&db, 1, "kisenosato");
+        testing::insert_banzuke(&db, basho_id, 10, "Hoshoryu", "Y1e");
+        testing::insert_banzuke(&db, basho_id, 11, "Wakatakakage", "S1e");
+        testing::insert_banzuke(&db, basho_id, 12, "Ura", "M2e");
+        (db, basho_id)
+    }
+
+    const PICKS: [Option<RikishiId>; 5] = [Some(10), Some(11), None, None, None];
+
+    #[test]
+    fn saves_the_captain_and_yusho_pick() {
+        let (mut db, basho_id) = captain_basho(true);
+        save_player_picks(&mut db, 1, basho_id, PICKS, Some(11), Some(12)).unwrap();
+        assert_eq!(captain_pick_for_player(&db, 1, basho_id).unwrap(), Some(11));
+        assert_eq!(yusho_pick_for_player(&db, 1, basho_id).unwrap(), Some(12));
+    }
+
+    #[test]
+    fn rejects_a_captain_when_the_rule_is_off() {
+        let (mut db, basho_id) = captain_basho(false);
+        assert!(matches!(
+            save_player_picks(&mut db, 1, basho_id, PICKS, Some(11), None),
+            Err(DataError::InvalidPicks)
+        ));
+        save_player_picks(&mut db, 1, basho_id, PICKS, None, None).unwrap();
+        assert_eq!(captain_pick_for_player(&db, 1, basho_id).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_a_captain_who_was_not_picked() {
+        let (mut db, basho_id) = captain_basho(true);
+        assert!(matches!(
+            save_player_picks(&mut db, 1, basho_id, PICKS, Some(12), None),
+            Err(DataError::InvalidPicks)
+        ));
+        assert_eq!(captain_pick_for_player(&db, 1, basho_id).unwrap(), None);
+    }
+}
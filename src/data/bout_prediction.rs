@@ -0,0 +1,366 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use itertools::Itertools;
+use rusqlite::{Connection, Result as SqlResult, Transaction};
+use std::collections::{HashMap, HashSet};
+
+use super::leaders::{assign_ord, Rankable};
+use super::{BashoId, DataError, Day, Player, PlayerId, Rank, RankSide, Result, RikishiId};
+
+/// Predictions for a day close when that day's bouts begin, at the same time of day as the basho start.
+pub fn day_deadline(basho_start: DateTime<Utc>, day: Day) -> DateTime<Utc> {
+    basho_start + TimeDelta::days(day as i64 - 1)
+}
+
+pub struct BoutRikishi {
+    pub id: RikishiId,
+    pub name: String,
+    pub rank: Rank,
+}
+
+/// A bout from `torikumi`, either scheduled or fought.
+pub struct ScheduledBout {
+    pub seq: u32,
+    pub east: BoutRikishi,
+    pub west: BoutRikishi,
+    pub winner: Option<RikishiId>,
+    /// The viewing player's predicted winner.
+    pub prediction: Option<RikishiId>,
+}
+
+impl ScheduledBout {
+    pub fn for_day(
+        db: &Connection,
+        basho_id: BashoId,
+        day: Day,
+        player_id: Option<PlayerId>,
+    ) -> Result<Vec<Self>> {
+        let rows = db
+            .prepare(
+                "
+                SELECT
+                    t.seq,
+                    t.side,
+                    t.rikishi_id,
+                    b.family_name,
+                    b.rank,
+                    t.win,
+                    EXISTS (
+                        SELECT 1
+                        FROM bout_prediction AS bp
+                        WHERE
+                            bp.player_id = :player_id
+                            AND bp.basho_id = t.basho_id
+                            AND bp.day = t.day
+                            AND bp.rikishi_id = t.rikishi_id
+                    ) AS is_prediction
+                FROM torikumi AS t
+                JOIN banzuke AS b USING (basho_id, rikishi_id)
+                WHERE t.basho_id = :basho_id AND t.day = :day
+                ORDER BY t.seq
+            ",
+            )?
+            .query_map(
+                named_params! {":basho_id": basho_id, ":day": day, ":player_id": player_id},
+                |row| {
+                    Ok((
+                        row.get::<_, u32>(0)?,
+                        row.get::<_, RankSide>(1)?,
+                        BoutRikishi {
+                            id: row.get(2)?,
+                            name: row.get(3)?,
+                            rank: row.get(4)?,
+                        },
+                        row.get::<_, Option<bool>>(5)?,
+                        row.get::<_, bool>(6)?,
+                    ))
+                },
+            )?
+            .collect::<SqlResult<Vec<_>>>()?;
+
+        Ok(rows
+            .into_iter()
+            .chunk_by(|row| row.0)
+            .into_iter()
+            .filter_map(|(seq, rows)| {
+                let mut east = None;
+                let mut west = None;
+                let mut winner = None;
+                let mut prediction = None;
+                for (_, side, rikishi, win, is_prediction) in rows {
+                    if win == Some(true) {
+                        winner = Some(rikishi.id);
+                    }
+                    if is_prediction {
+                        prediction = Some(rikishi.id);
+                    }
+                    match side {
+                        RankSide::East => east = Some(rikishi),
+                        RankSide::West => west = Some(rikishi),
+                    }
+                }
+                Some(ScheduledBout {
+                    seq,
+                    east: east?,
+                    west: west?,
+                    winner,
+                    prediction,
+                })
+            })
+            .collect())
+    }
+
+    pub fn is_correct(&self) -> Option<bool> {
+        Some(self.prediction? == self.winner?)
+    }
+}
+
+/// The first day with scheduled bouts still open for predictions.
+pub fn next_open_day(
+    db: &Connection,
+    basho_id: BashoId,
+    basho_start: DateTime<Utc>,
+) -> Result<Option<Day>> {
+    let days: Vec<Day> = db
+        .prepare(
+            "
+            SELECT DISTINCT day
+            FROM torikumi
+            WHERE basho_id = ? AND win IS NULL
+            ORDER BY day
+        ",
+        )?
+        .query_map(params![basho_id], |row| row.get(0))?
+        .collect::<SqlResult<_>>()?;
+    let now = Utc::now();
+    Ok(days
+        .into_iter()
+        .find(|day| day_deadline(basho_start, *day) > now))
+}
+
+/// Days that have predictions scored, oldest first.
+pub fn scored_days(db: &Connection, basho_id: BashoId) -> Result<Vec<Day>> {
+    Ok(db
+        .prepare(
+            "
+            SELECT DISTINCT day
+            FROM bout_prediction
+            WHERE basho_id = ? AND is_correct IS NOT NULL
+            ORDER BY day
+        ",
+        )?
+        .query_map(params![basho_id], |row| row.get(0))?
+        .collect::<SqlResult<_>>()?)
+}
+
+/// Replaces a player's predictions for one day. Each predicted winner must be in a different scheduled bout.
+pub fn save_predictions(
+    db: &mut Connection,
+    player_id: PlayerId,
+    basho_id: BashoId,
+    day: Day,
+    winners: &[RikishiId],
+) -> Result<()> {
+    let txn = db.transaction()?;
+    let start_date: DateTime<Utc> = txn.query_row(
+        "SELECT start_date FROM basho WHERE id = ?",
+        params![basho_id],
+        |row| row.get(0),
+    )?;
+    if day_deadline(start_date, day) < Utc::now() {
+        return Err(DataError::DayHasStarted);
+    }
+
+    let bout_by_rikishi: HashMap<RikishiId, u32> = txn
+        .prepare(
+            "
+            SELECT rikishi_id, seq
+            FROM torikumi
+            WHERE basho_id = ? AND day = ? AND win IS NULL
+        ",
+        )?
+        .query_map(params![basho_id, day], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<SqlResult<_>>()?;
+    let mut bouts = HashSet::new();
+    for rikishi_id in winners {
+        match bout_by_rikishi.get(rikishi_id) {
+            Some(seq) if bouts.insert(*seq) => (),
+            _ => return Err(DataError::InvalidPicks),
+        }
+    }
+
+    txn.execute(
+        "DELETE FROM bout_prediction WHERE player_id = ? AND basho_id = ? AND day = ?",
+        params![player_id, basho_id, day],
+    )?;
+    let now = Utc::now();
+    for rikishi_id in winners {
+        txn.execute(
+            "
+            INSERT INTO bout_prediction (player_id, basho_id, day, rikishi_id, prediction_date)
+            VALUES (?, ?, ?, ?, ?)
+        ",
+            params![player_id, basho_id, day, rikishi_id, now],
+        )?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+/// Marks each prediction for the day against the results in `torikumi`. Predictions for bouts that weren't fought stay unscored.
+pub fn score_day(txn: &Transaction, basho_id: BashoId, day: Day) -> Result<()> {
+    let count = txn.execute(
+        "
+        UPDATE bout_prediction
+        SET is_correct = (
+            SELECT t.win
+            FROM torikumi AS t
+            WHERE
+                t.basho_id = bout_prediction.basho_id
+                AND t.day = bout_prediction.day
+                AND t.rikishi_id = bout_prediction.rikishi_id
+        )
+        WHERE basho_id = ? AND day = ?
+    ",
+        params![basho_id, day],
+    )?;
+    debug!(
+        "scored {} bout predictions for {} day {}",
+        count, basho_id, day
+    );
+    Ok(())
+}
+
+pub struct PredictionStanding {
+    pub ord: usize,
+    pub player: Player,
+    pub correct: u32,
+    pub scored: u32,
+}
+
+impl Rankable for PredictionStanding {
+    fn get_score(&self) -> i32 {
+        self.correct as i32
+    }
+
+    fn set_rank(&mut self, ord: usize) {
+        self.ord = ord;
+    }
+}
+
+impl PredictionStanding {
+    /// Standings for one day, or for the whole basho when `day` is none.
+    pub fn for_basho(db: &Connection, basho_id: BashoId, day: Option<Day>) -> Result<Vec<Self>> {
+        let mut standings = db
+            .prepare(
+                "
+                SELECT
+                    player.*,
+                    SUM(bp.is_correct) AS correct,
+                    COUNT(bp.is_correct) AS scored
+                FROM bout_prediction AS bp
+                JOIN player_info AS player ON player.id = bp.player_id
+                WHERE bp.basho_id = :basho_id AND (:day IS NULL OR bp.day = :day)
+                GROUP BY player.id
+                HAVING scored > 0
+                ORDER BY correct DESC, scored ASC, player.id
+            ",
+            )?
+            .query_map(named_params! {":basho_id": basho_id, ":day": day}, |row| {
+                Ok(Self {
+                    ord: 0,
+                    player: Player::from_row(row)?,
+                    correct: row.get("correct")?,
+                    scored: row.get("scored")?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        assign_ord(&mut standings.iter_mut());
+        Ok(standings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::testing;
+
+    const BASHO: BashoId = BashoId {
+        year: 2026,
+        month: 11,
+    };
+
+    /// A basho starting at `start_date` with Hoshoryu facing Onosato and Kirishima facing Takayasu on day 2.
+    fn setup(start_date: DateTime<Utc>) -> Connection {
+        let db = testing::test_db();
+        testing::insert_basho(&db, BASHO, start_date);
+        testing::insert_player(&db, 1, "kisenosato");
+        for (seq, side, id, name, rank) in [
+            (1, RankSide::East, 1, "Hoshoryu", "Y1e"),
+            (1, RankSide::West, 2, "Onosato", "O1e"),
+            (2, RankSide::East, 3, "Kirishima", "S1e"),
+            (2, RankSide::West, 4, "Takayasu", "M1w"),
+        ] {
+            testing::insert_banzuke(&db, BASHO, id, name, rank);
+            db.execute(
+                "
+                    INSERT INTO torikumi (basho_id, day, seq, side, rikishi_id)
+                    VALUES (?, 2, ?, ?, ?)
+                ",
+                params![BASHO, seq, side, id],
+            )
+            .unwrap();
+        }
+        db
+    }
+
+    fn predictions(db: &Connection) -> Vec<(RikishiId, Option<bool>)> {
+        db.prepare("SELECT rikishi_id, is_correct FROM bout_prediction ORDER BY rikishi_id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<SqlResult<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn predictions_close_when_the_day_starts() {
+        // Day 2 began an hour ago.
+        let mut db = setup(Utc::now() - TimeDelta::days(1) - TimeDelta::hours(1));
+        assert!(matches!(
+            save_predictions(&mut db, 1, BASHO, 2, &[1]),
+            Err(DataError::DayHasStarted)
+        ));
+    }
+
+    #[test]
+    fn one_prediction_per_bout() {
+        let mut db = setup(Utc::now() + TimeDelta::hours(1));
+        assert!(matches!(
+            save_predictions(&mut db, 1, BASHO, 2, &[1, 2]),
+            Err(DataError::InvalidPicks)
+        ));
+        assert!(matches!(
+            save_predictions(&mut db, 1, BASHO, 2, &[5]),
+            Err(DataError::InvalidPicks)
+        ));
+        save_predictions(&mut db, 1, BASHO, 2, &[1, 4]).unwrap();
+        assert_eq!(predictions(&db), [(1, None), (4, None)]);
+    }
+
+    #[test]
+    fn score_day_marks_predictions_against_results() {
+        let mut db = setup(Utc::now() + TimeDelta::hours(1));
+        save_predictions(&mut db, 1, BASHO, 2, &[1, 4]).unwrap();
+        db.execute(
+            "UPDATE torikumi SET win = (rikishi_id IN (1, 3)) WHERE basho_id = ?",
+            params![BASHO],
+        )
+        .unwrap();
+        let txn = db.transaction().unwrap();
+        score_day(&txn, BASHO, 2).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(predictions(&db), [(1, Some(true)), (4, Some(false))]);
+        let standings = PredictionStanding::for_basho(&db, BASHO, None).unwrap();
+        assert_eq!((standings[0].correct, standings[0].scored), (1, 2));
+    }
+}
@@ -1,19 +1,35 @@
 use std::ops::RangeInclusive;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use itertools::Itertools;
-use rusqlite::{Connection, OptionalExtension, Result as SqlResult, Row};
+use rand::random;
+use rusqlite::{Connection, OptionalExtension, Result as SqlResult, Row, Transaction};
 use slug_intl::slugify;
 
+use super::leaders::{assign_ord, Rankable};
 use super::{BashoId, BashoInfo, DataError, Player, PlayerId, Result};
 
 pub const MEMBER_MAX: usize = 50;
 pub const JOIN_MAX: usize = 10;
 pub const HOST_MAX: usize = 5;
 pub const NAME_LENGTH: RangeInclusive<usize> = 3..=30;
+pub const INVITE_DAYS: i64 = 7;
+pub const MESSAGE_LENGTH: RangeInclusive<usize> = 1..=200;
+/// Messages go to every member's devices, so a heya can only send this many per `MESSAGE_RATE_MINUTES`.
+pub const MESSAGE_RATE_MAX: usize = 3;
+pub const MESSAGE_RATE_MINUTES: i64 = 60;
+
+/// Only this many of a heya's best scores count toward its league score, so that big heya don't win just by having more members.
+pub const LEAGUE_SCORING_MEMBERS: usize = 5;
+pub const HEYA_CUP_EMOJI: &str = "🏯";
 
 pub type HeyaId = i64;
 
+/// A heya's league score: the mean of its top `LEAGUE_SCORING_MEMBERS` members' wins, or of every entrant's when it has fewer.
+fn league_score(total: u16, entrants: usize) -> f64 {
+    total as f64 / entrants.clamp(1, LEAGUE_SCORING_MEMBERS) as f64
+}
+
 #[derive(Debug)]
 pub struct Heya {
     pub id: HeyaId,
@@ -25,6 +41,7 @@ pub struct Heya {
     pub members: Option<Vec<Member>>, // might not be populated in all cases
     pub recent_scores_bashos: Option<Vec<BashoId>>,
     pub recruit_date: Option<DateTime<Utc>>, // of the player for `for_player`
+    pub is_cohost: bool,                     // of the player for `for_player`
 }
 
 impl Heya {
@@ -58,6 +75,7 @@ impl Heya {
                 member_count: row.get::<_, isize>("member_count")?.cast_unsigned(),
                 recent_scores_bashos: None,
                 recruit_date: None,
+                is_cohost: false,
             })
         })?
         .collect()
@@ -116,6 +134,7 @@ impl Heya {
                         member_count: row.get::<_, isize>("member_count")?.cast_unsigned(),
                         recent_scores_bashos: None,
                         recruit_date: None,
+                        is_cohost: false,
                     })
                 },
             )
@@ -159,7 +178,8 @@ impl Heya {
                         WHERE hp2.heya_id = heya.id
                     ) AS member_count,
                     oyakata.*,
-                    hp.recruit_date
+                    hp.recruit_date,
+                    hp.is_cohost
                 FROM heya_player AS hp
                 JOIN heya ON heya.id = hp.heya_id
                 JOIN player_info AS oyakata ON oyakata.id = heya.oyakata_player_id
@@ -177,6 +197,7 @@ impl Heya {
                 member_count: row.get::<_, isize>("member_count")?.cast_unsigned(),
                 recent_scores_bashos: None,
                 recruit_date: Some(row.get("recruit_date")?),
+                is_cohost: row.get("is_cohost")?,
             })
         })?
         .collect()
@@ -186,11 +207,30 @@ impl Heya {
         format!("/heya/{}", self.slug)
     }
 
+    /// Looks up a heya by a slug it used before being renamed.
+    pub fn with_old_slug(db: &Connection, slug: &str, include_members: bool) -> Result<Self> {
+        let heya_id: Option<HeyaId> = db
+            .query_row(
+                "SELECT heya_id FROM heya_slug_history WHERE slug = ?",
+                params![slug],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match heya_id {
+            Some(id) => Self::with_id(db, id, include_members),
+            None => Err(DataError::HeyaNotFound {
+                slug: Some(slug.to_string()),
+                id: None,
+            }),
+        }
+    }
+
     pub fn new(db: &mut Connection, name: &str, oyakata: PlayerId) -> Result<Self> {
         Self::validate_name(name)?;
         let slug = slugify(name);
         let now = Utc::now();
         let txn = db.transaction()?;
+        Self::validate_slug(&txn, &slug, None)?;
         txn.prepare(
             "
                 INSERT INTO heya (name, slug, oyakata_player_id, create_date)
@@ -214,15 +254,36 @@ impl Heya {
         Ok(heya)
     }
 
-    pub fn set_name(&mut self, db: &Connection, name: &str) -> Result<()> {
+    pub fn set_name(&mut self, db: &mut Connection, name: &str) -> Result<()> {
         Self::validate_name(name)?;
         let slug = slugify(name);
-        db.prepare(
+        let txn = db.transaction()?;
+        if slug != self.slug {
+            Self::validate_slug(&txn, &slug, Some(self.id))?;
+            txn.prepare(
+                "
+                    INSERT OR REPLACE INTO heya_slug_history (slug, heya_id, retire_date)
+                    VALUES (?, ?, ?)
+                ",
+            )?
+            .execute(params![self.slug, self.id, Utc::now()])?;
+            // Reclaiming one of our own old slugs:
+            txn.prepare(
+                "
+                    DELETE FROM heya_slug_history WHERE slug = ?
+                ",
+            )?
+            .execute(params![slug])?;
+        }
+        txn.prepare(
             "
                 UPDATE heya SET name = ?, slug = ? WHERE id = ?
             ",
         )?
         .execute(params![name, slug, self.id])?;
+        txn.commit()?;
+        self.name = name.to_string();
+        self.slug = slug;
         Ok(())
     }
 
@@ -235,6 +296,13 @@ impl Heya {
             ",
         )?
         .execute(params![self.id, player, Utc::now()])?;
+        txn.prepare(
+            "
+                DELETE FROM heya_join_request
+                WHERE heya_id = ? AND player_id = ?
+            ",
+        )?
+        .execute(params![self.id, player])?;
 
         Self::validate_quota(&txn, player)?;
 
@@ -248,7 +316,8 @@ impl Heya {
     pub fn delete_member(&mut self, db: &mut Connection, player: PlayerId) -> Result<()> {
         if self.oyakata.id == player {
             return Err(DataError::HeyaIntegrity {
-                what: "Oyakata can’t leave heya".to_string(),
+                what: "Oyakata can’t leave heya; transfer it to another member or dissolve it"
+                    .to_string(),
             });
         }
 
@@ -266,6 +335,281 @@ impl Heya {
         Ok(())
     }
 
+    fn is_member(db: &Connection, heya_id: HeyaId, player: PlayerId) -> Result<bool> {
+        Ok(db.query_row(
+            "
+                SELECT EXISTS(
+                    SELECT 1 FROM heya_player
+                    WHERE heya_id = ? AND player_id = ?
+                )
+            ",
+            params![heya_id, player],
+            |row| row.get(0),
+        )?)
+    }
+
+    pub fn is_cohost(&self, db: &Connection, player: PlayerId) -> Result<bool> {
+        Ok(db
+            .query_row(
+                "
+                    SELECT is_cohost FROM heya_player
+                    WHERE heya_id = ? AND player_id = ?
+                ",
+                params![self.id, player],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(false))
+    }
+
+    /// The oyakata and co-hosts can add and remove members.
+    pub fn can_manage_members(&self, db: &Connection, player: PlayerId) -> Result<bool> {
+        Ok(self.oyakata.id == player || self.is_cohost(db, player)?)
+    }
+
+    pub fn set_cohost(&mut self, db: &Connection, player: PlayerId, is_cohost: bool) -> Result<()> {
+        if self.oyakata.id == player {
+            return Err(DataError::HeyaIntegrity {
+                what: "Oyakata can’t be a co-host".to_string(),
+            });
+        }
+        let count = db
+            .prepare(
+                "
+                    UPDATE heya_player SET is_cohost = ?
+                    WHERE heya_id = ? AND player_id = ?
+                ",
+            )?
+            .execute(params![is_cohost, self.id, player])?;
+        if count == 0 {
+            return Err(DataError::HeyaIntegrity {
+                what: format!("Player {} is not a member of {}", player, self.name),
+            });
+        }
+        self.members = None;
+        Ok(())
+    }
+
+    pub fn transfer_ownership(&mut self, db: &mut Connection, player: PlayerId) -> Result<()> {
+        let txn = db.transaction()?;
+        if !Self::is_member(&txn, self.id, player)? {
+            return Err(DataError::HeyaIntegrity {
+                what: format!("Player {} is not a member of {}", player, self.name),
+            });
+        }
+        txn.prepare(
+            "
+                UPDATE heya SET oyakata_player_id = ? WHERE id = ?
+            ",
+        )?
+        .execute(params![player, self.id])?;
+        txn.prepare(
+            "
+                UPDATE heya_player SET is_cohost = 0
+                WHERE heya_id = ? AND player_id = ?
+            ",
+        )?
+        .execute(params![self.id, player])?;
+
+        Self::validate_quota(&txn, player)?;
+        info!(
+            "heya {} transferred from player {} to player {}",
+            self.id, self.oyakata.id, player
+        );
+        *self = Self::with_id(&txn, self.id, false)?;
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Deletes the heya along with its members, invites, join requests and league history. Returns the former members other than the oyakata so they can be notified.
+    pub fn dissolve(self, db: &mut Connection) -> Result<Vec<PlayerId>> {
+        let txn = db.transaction()?;
+        let members = txn
+            .prepare(
+                "
+                    SELECT player_id FROM heya_player
+                    WHERE heya_id = ? AND player_id != ?
+                ",
+            )?
+            .query_map(params![self.id, self.oyakata.id], |row| row.get(0))?
+            .collect::<SqlResult<Vec<PlayerId>>>()?;
+        // Members, invites and everything else that references the heya go with it via ON DELETE CASCADE.
+        txn.execute("DELETE FROM heya WHERE id = ?", params![self.id])?;
+        txn.commit()?;
+        info!(
+            "heya {} ({}) dissolved by player {}",
+            self.id, self.name, self.oyakata.id
+        );
+        Ok(members)
+    }
+
+    pub fn create_invite(&self, db: &Connection, created_by: PlayerId) -> Result<HeyaInvite> {
+        let now = Utc::now();
+        let invite = HeyaInvite {
+            token: format!("{:032x}", random::<u128>()),
+            heya_id: self.id,
+            heya_slug: self.slug.clone(),
+            expire_date: now + TimeDelta::days(INVITE_DAYS),
+        };
+        db.prepare(
+            "
+                INSERT INTO heya_invite (token, heya_id, created_by, create_date, expire_date)
+                VALUES (?, ?, ?, ?, ?)
+            ",
+        )?
+        .execute(params![
+            invite.token,
+            self.id,
+            created_by,
+            now,
+            invite.expire_date
+        ])?;
+        Ok(invite)
+    }
+
+    pub fn invites(&self, db: &Connection) -> Result<Vec<HeyaInvite>> {
+        let now = Utc::now();
+        Ok(db
+            .prepare(
+                "
+                    SELECT i.token, i.heya_id, i.expire_date, heya.slug AS heya_slug
+                    FROM heya_invite AS i
+                    JOIN heya ON heya.id = i.heya_id
+                    WHERE i.heya_id = ?
+                    ORDER BY i.expire_date DESC
+                ",
+            )?
+            .query_map(params![self.id], HeyaInvite::from_row)?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .filter(|invite| invite.expire_date > now)
+            .collect())
+    }
+
+    /// Returns the invite only if it belongs to this heya and hasn't expired.
+    pub fn valid_invite(&self, db: &Connection, token: &str) -> Result<Option<HeyaInvite>> {
+        Ok(db
+            .query_row(
+                "
+                    SELECT i.token, i.heya_id, i.expire_date, heya.slug AS heya_slug
+                    FROM heya_invite AS i
+                    JOIN heya ON heya.id = i.heya_id
+                    WHERE i.token = ? AND i.heya_id = ?
+                ",
+                params![token, self.id],
+                HeyaInvite::from_row,
+            )
+            .optional()?
+            .filter(|invite| invite.expire_date > Utc::now()))
+    }
+
+    pub fn revoke_invite(&self, db: &Connection, token: &str) -> Result<()> {
+        db.prepare(
+            "
+                DELETE FROM heya_invite
+                WHERE token = ? AND heya_id = ?
+            ",
+        )?
+        .execute(params![token, self.id])?;
+        Ok(())
+    }
+
+    pub fn join_with_invite(
+        &mut self,
+        db: &mut Connection,
+        token: &str,
+        player: PlayerId,
+    ) -> Result<()> {
+        if self.valid_invite(db, token)?.is_none() {
+            return Err(DataError::HeyaIntegrity {
+                what: "Invite link is invalid or has expired".to_string(),
+            });
+        }
+        self.add_member(db, player)
+    }
+
+    pub fn request_join(&self, db: &Connection, player: PlayerId) -> Result<()> {
+        let player_heyas = Self::for_player(db, player)?;
+        if player_heyas.iter().any(|h| h.id == self.id) {
+            return Err(DataError::HeyaIntegrity {
+                what: format!("Already a member of {}", self.name),
+            });
+        }
+        if player_heyas.len() >= JOIN_MAX {
+            return Err(DataError::HeyaIntegrity {
+                what: format!("Player {} in too many heyas (max {})", player, JOIN_MAX),
+            });
+        }
+        if self.member_count >= MEMBER_MAX {
+            return Err(DataError::HeyaIntegrity {
+                what: format!("Heya {} is full (max {} members)", self.name, MEMBER_MAX),
+            });
+        }
+        db.prepare(
+            "
+                INSERT INTO heya_join_request (heya_id, player_id, request_date)
+                VALUES (?, ?, ?)
+            ",
+        )?
+        .execute(params![self.id, player, Utc::now()])?;
+        Ok(())
+    }
+
+    pub fn has_join_request(&self, db: &Connection, player: PlayerId) -> Result<bool> {
+        Ok(db.query_row(
+            "
+                SELECT EXISTS(
+                    SELECT 1 FROM heya_join_request
+                    WHERE heya_id = ? AND player_id = ?
+                )
+            ",
+            params![self.id, player],
+            |row| row.get(0),
+        )?)
+    }
+
+    pub fn join_requests(&self, db: &Connection) -> Result<Vec<JoinRequest>> {
+        Ok(db
+            .prepare(
+                "
+                    SELECT p.*, jr.request_date
+                    FROM heya_join_request AS jr
+                    JOIN player_info AS p ON p.id = jr.player_id
+                    WHERE jr.heya_id = ?
+                    ORDER BY jr.request_date
+                ",
+            )?
+            .query_map(params![self.id], |row| {
+                Ok(JoinRequest {
+                    player: Player::from_row(row)?,
+                    request_date: row.get("request_date")?,
+                })
+            })?
+            .collect::<SqlResult<_>>()?)
+    }
+
+    /// Approving a request is just adding the member, which also clears the request.
+    pub fn approve_join_request(&mut self, db: &mut Connection, player: PlayerId) -> Result<()> {
+        if !self.has_join_request(db, player)? {
+            return Err(DataError::HeyaIntegrity {
+                what: format!("No pending request from player {}", player),
+            });
+        }
+        self.add_member(db, player)
+    }
+
+    pub fn decline_join_request(&self, db: &Connection, player: PlayerId) -> Result<()> {
+        db.prepare(
+            "
+                DELETE FROM heya_join_request
+                WHERE heya_id = ? AND player_id = ?
+            ",
+        )?
+        .execute(params![self.id, player])?;
+        Ok(())
+    }
+
     fn validate_name(name: &str) -> Result<()> {
         if NAME_LENGTH.contains(&name.len()) {
             Ok(())
@@ -280,6 +624,101 @@ impl Heya {
         }
     }
 
+    /// Records an oyakata's message before it's pushed to members, refusing it if the heya has sent too many recently.
+    pub fn record_message(
+        db: &mut Connection,
+        heya_id: HeyaId,
+        player: PlayerId,
+        message: &str,
+    ) -> Result<()> {
+        Self::validate_message(message)?;
+        let txn = db.transaction()?;
+        Self::validate_message_rate(&txn, heya_id)?;
+        txn.execute(
+            "
+                INSERT INTO heya_message (heya_id, player_id, message, send_date)
+                VALUES (?, ?, ?, ?)
+            ",
+            params![heya_id, player, message, Utc::now()],
+        )?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn validate_message(message: &str) -> Result<()> {
+        if MESSAGE_LENGTH.contains(&message.chars().count()) {
+            Ok(())
+        } else {
+            Err(DataError::HeyaIntegrity {
+                what: format!(
+                    "Message must be {} to {} characters",
+                    MESSAGE_LENGTH.start(),
+                    MESSAGE_LENGTH.end()
+                ),
+            })
+        }
+    }
+
+    fn validate_message_rate(db: &Connection, heya_id: HeyaId) -> Result<()> {
+        let since = Utc::now() - TimeDelta::minutes(MESSAGE_RATE_MINUTES);
+        let recent = db
+            .prepare(
+                "
+                    SELECT send_date FROM heya_message
+                    WHERE heya_id = ?
+                    ORDER BY send_date DESC
+                    LIMIT ?
+                ",
+            )?
+            .query_map(params![heya_id, MESSAGE_RATE_MAX as u32], |row| {
+                row.get::<_, DateTime<Utc>>(0)
+            })?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .filter(|date| *date > since)
+            .count();
+        if recent < MESSAGE_RATE_MAX {
+            Ok(())
+        } else {
+            Err(DataError::HeyaIntegrity {
+                what: format!(
+                    "Too many messages; a heya can send {} every {} minutes",
+                    MESSAGE_RATE_MAX, MESSAGE_RATE_MINUTES
+                ),
+            })
+        }
+    }
+
+    /// Slugs must be unique across current and old slugs of every heya, so that old links never change which heya they point to. All-digit slugs are reserved for numeric heya IDs.
+    fn validate_slug(db: &Connection, slug: &str, heya_id: Option<HeyaId>) -> Result<()> {
+        if slug.parse::<HeyaId>().is_ok() {
+            return Err(DataError::HeyaIntegrity {
+                what: "Name can’t be only digits".to_string(),
+            });
+        }
+        let taken: bool = db.query_row(
+            "
+                SELECT
+                    EXISTS(SELECT 1 FROM heya WHERE slug = :slug AND id IS NOT :heya_id)
+                    OR EXISTS(
+                        SELECT 1 FROM heya_slug_history
+                        WHERE slug = :slug AND heya_id IS NOT :heya_id
+                    )
+            ",
+            named_params! {
+                ":slug": slug,
+                ":heya_id": heya_id,
+            },
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Err(DataError::HeyaIntegrity {
+                what: format!("The name “{}” is taken by another heya", slug),
+            });
+        }
+        Ok(())
+    }
+
     fn validate_quota(db: &Connection, player: PlayerId) -> Result<()> {
         let player_heyas = Self::for_player(db, player)?;
         if player_heyas.len() > JOIN_MAX {
@@ -312,10 +751,40 @@ impl Heya {
     }
 }
 
+#[derive(Debug)]
+pub struct HeyaInvite {
+    pub token: String,
+    pub heya_id: HeyaId,
+    pub heya_slug: String,
+    pub expire_date: DateTime<Utc>,
+}
+
+impl HeyaInvite {
+    fn from_row(row: &Row) -> SqlResult<Self> {
+        Ok(Self {
+            token: row.get("token")?,
+            heya_id: row.get("heya_id")?,
+            heya_slug: row.get("heya_slug")?,
+            expire_date: row.get("expire_date")?,
+        })
+    }
+
+    pub fn url_path(&self) -> String {
+        format!("/heya/{}?invite={}", self.heya_slug, self.token)
+    }
+}
+
+#[derive(Debug)]
+pub struct JoinRequest {
+    pub player: Player,
+    pub request_date: DateTime<Utc>,
+}
+
 #[derive(Debug)]
 pub struct Member {
     pub player: Player,
     pub is_oyakata: bool,
+    pub is_cohost: bool,
     pub is_self: bool,
     pub recruit_date: DateTime<Utc>,
     pub recent_scores: Vec<Option<u8>>,
@@ -330,6 +799,7 @@ impl Member {
             player,
             recruit_date: row.get("recruit_date")?,
             is_oyakata: row.get("is_oyakata")?,
+            is_cohost: row.get("is_cohost")?,
             is_self: false,
             recent_scores: row
                 .get::<_, String>("recent_scores")?
@@ -361,6 +831,7 @@ impl Member {
                         p.*,
                         pr.rank,
                         hp.recruit_date,
+                        hp.is_cohost,
                         p.id = h.oyakata_player_id AS is_oyakata,
                         (
                             SELECT GROUP_CONCAT(
@@ -401,3 +872,290 @@ impl Member {
             .sum()
     }
 }
+
+/// A heya's place in the inter-heya league for one basho. Live during the basho, and read back from the `heya_basho_result` snapshot once the basho is finalized.
+#[derive(Debug)]
+pub struct HeyaStanding {
+    pub heya_id: HeyaId,
+    pub name: String,
+    pub slug: String,
+    pub ord: usize,
+    pub total: u16,
+    pub entrants: usize,
+    pub is_final: bool,
+}
+
+impl Rankable for HeyaStanding {
+    fn get_score(&self) -> i32 {
+        // Exact for every divisor up to `LEAGUE_SCORING_MEMBERS`, so equal means tie.
+        (self.score() * 60.0).round() as i32
+    }
+
+    fn set_rank(&mut self, ord: usize) {
+        self.ord = ord;
+    }
+}
+
+impl HeyaStanding {
+    pub fn for_basho(db: &Connection, basho_id: BashoId) -> Result<Vec<Self>> {
+        let snapshot = Self::fetch_final(db, basho_id)?;
+        if snapshot.is_empty() {
+            Self::compute(db, basho_id)
+        } else {
+            Ok(snapshot)
+        }
+    }
+
+    pub fn score(&self) -> f64 {
+        league_score(self.total, self.entrants)
+    }
+
+    pub fn has_cup(&self) -> bool {
+        self.is_final && self.ord == 1
+    }
+
+    pub fn url_path(&self) -> String {
+        format!("/heya/{}", self.slug)
+    }
+
+    fn compute(db: &Connection, basho_id: BashoId) -> Result<Vec<Self>> {
+        let mut standings: Vec<Self> = db
+            .prepare(
+                "
+                SELECT
+                    heya.id,
+                    heya.name,
+                    heya.slug,
+                    br.wins
+                FROM heya
+                JOIN heya_player AS hp ON hp.heya_id = heya.id
+                JOIN basho_result AS br ON br.player_id = hp.player_id AND br.basho_id = ?
+                ORDER BY heya.id, br.wins DESC
+            ",
+            )?
+            .query_map(params![basho_id], |row| {
+                Ok((
+                    row.get::<_, HeyaId>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, u16>(3)?,
+                ))
+            })?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .chunk_by(|(heya_id, _, _, _)| *heya_id)
+            .into_iter()
+            .map(|(heya_id, rows)| {
+                let rows: Vec<_> = rows.collect();
+                Self {
+                    heya_id,
+                    name: rows[0].1.clone(),
+                    slug: rows[0].2.clone(),
+                    ord: 0,
+                    total: rows
+                        .iter()
+                        .take(LEAGUE_SCORING_MEMBERS)
+                        .map(|(_, _, _, wins)| wins)
+                        .sum(),
+                    entrants: rows.len(),
+                    is_final: false,
+                }
+            })
+            .sorted_by(|a, b| {
+                b.get_score()
+                    .cmp(&a.get_score())
+                    .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+            })
+            .collect();
+        assign_ord(&mut standings.iter_mut());
+        Ok(standings)
+    }
+
+    fn fetch_final(db: &Connection, basho_id: BashoId) -> Result<Vec<Self>> {
+        db.prepare(
+            "
+                SELECT
+                    heya.id,
+                    heya.name,
+                    heya.slug,
+                    r.total,
+                    r.rank,
+                    r.entrants
+                FROM heya_basho_result AS r
+                JOIN heya ON heya.id = r.heya_id
+                WHERE r.basho_id = ?
+                ORDER BY r.rank, heya.name COLLATE NOCASE
+            ",
+        )?
+        .query_map(params![basho_id], |row| {
+            Ok(Self {
+                heya_id: row.get("id")?,
+                name: row.get("name")?,
+                slug: row.get("slug")?,
+                total: row.get("total")?,
+                ord: row.get::<_, u32>("rank")? as usize,
+                entrants: row.get::<_, u32>("entrants")? as usize,
+                is_final: true,
+            })
+        })?
+        .collect::<SqlResult<_>>()
+        .map_err(|e| e.into())
+    }
+
+    /// Snapshots the league standings for a basho; the heya ranked #1 wins the heya cup.
+    pub fn upsert_final(txn: &Transaction, basho_id: BashoId) -> Result<()> {
+        txn.execute(
+            "
+                DELETE FROM heya_basho_result
+                WHERE basho_id = ?
+            ",
+            params![basho_id],
+        )?;
+        let standings = Self::compute(txn, basho_id)?;
+        info!(
+            "upsert_final heya standings for {} heyas in {}",
+            standings.len(),
+            basho_id
+        );
+        let mut stmt = txn.prepare(
+            "
+                INSERT INTO heya_basho_result (basho_id, heya_id, total, rank, entrants)
+                VALUES (?, ?, ?, ?, ?)
+            ",
+        )?;
+        for s in standings {
+            if s.ord == 1 {
+                debug!("  ! awarding heya cup to {}", s.name);
+            }
+            stmt.execute(params![
+                basho_id,
+                s.heya_id,
+                s.total,
+                s.ord as u32,
+                s.entrants as u32
+            ])?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct HeyaBashoResult {
+    pub basho_id: BashoId,
+    pub ord: usize,
+    pub total: u16,
+    pub entrants: usize,
+    pub heya_count: usize,
+}
+
+impl HeyaBashoResult {
+    pub fn for_heya(db: &Connection, heya_id: HeyaId) -> Result<Vec<Self>> {
+        db.prepare(
+            "
+                SELECT
+                    r.basho_id,
+                    r.rank,
+                    r.total,
+                    r.entrants,
+                    (
+                        SELECT COUNT(*) FROM heya_basho_result AS r2
+                        WHERE r2.basho_id = r.basho_id
+                    ) AS heya_count
+                FROM heya_basho_result AS r
+                WHERE r.heya_id = ?
+                ORDER BY r.basho_id DESC
+            ",
+        )?
+        .query_map(params![heya_id], |row| {
+            Ok(Self {
+                basho_id: row.get("basho_id")?,
+                ord: row.get::<_, u32>("rank")? as usize,
+                total: row.get("total")?,
+                entrants: row.get::<_, u32>("entrants")? as usize,
+                heya_count: row.get::<_, u32>("heya_count")? as usize,
+            })
+        })?
+        .collect::<SqlResult<_>>()
+        .map_err(|e| e.into())
+    }
+
+    pub fn score(&self) -> f64 {
+        league_score(self.total, self.entrants)
+    }
+
+    pub fn has_cup(&self) -> bool {
+        self.ord == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::testing;
+
+    #[test]
+    fn small_heya_score_the_mean_of_their_entrants() {
+        let db = testing::test_db();
+        let basho_id = BashoId::from(202611);
+        testing::insert_basho(&db, basho_id, Utc::now() - TimeDelta::days(15));
+        let heya = [
+            (1, "Small", vec![10, 10, 10]),
+            (2, "Big", vec![9, 9, 9, 9, 9, 9]),
+        ];
+        let mut player_id = 0;
+        for (heya_id, name, scores) in heya {
+            for (i, wins) in scores.into_iter().enumerate() {
+                player_id += 1;
+                testing::insert_player(&db, player_id, &format!("player{player_id}"));
+                if i == 0 {
+                    db.execute(
+                        "
+                            INSERT INTO heya (id, name, slug, oyakata_player_id, create_date)
+                            VALUES (?, ?, ?, ?, ?)
+                        ",
+                        params![heya_id, name, name.to_lowercase(), player_id, Utc::now()],
+                    )
+                    .unwrap();
+                }
+                db.execute(
+                    "INSERT INTO heya_player (player_id, heya_id, recruit_date) VALUES (?, ?, ?)",
+                    params![player_id, heya_id, Utc::now()],
+                )
+                .unwrap();
+                db.execute(
+                    "INSERT INTO basho_result (basho_id, player_id, wins, rank) VALUES (?, ?, ?, 0)",
+                    params![basho_id, player_id, wins],
+                )
+                .unwrap();
+            }
+        }
+
+        let standings = HeyaStanding::compute(&db, basho_id).unwrap();
+        let summary: Vec<_> = standings
+            .iter()
+            .map(|s| (s.name.as_str(), s.ord, s.entrants, s.score()))
+            .collect();
+        assert_eq!(summary, [("Small", 1, 3, 10.0), ("Big", 2, 6, 9.0)]);
+    }
+
+    #[test]
+    fn messages_are_rate_limited_per_heya() {
+        let mut db = testing::test_db();
+        testing::insert_player(&db, 1, "kisenosato");
+        db.execute(
+            "
+                INSERT INTO heya (id, name, slug, oyakata_player_id, create_date)
+                VALUES (1, 'Tagonoura', 'tagonoura', 1, ?)
+            ",
+            params![Utc::now()],
+        )
+        .unwrap();
+        for _ in 0..MESSAGE_RATE_MAX {
+            Heya::record_message(&mut db, 1, 1, "keiko at 7").unwrap();
+        }
+        assert!(matches!(
+            Heya::record_message(&mut db, 1, 1, "keiko at 8"),
+            Err(DataError::HeyaIntegrity { .. })
+        ));
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use itertools::Itertools;
+use rand::random;
+use rusqlite::{Connection, OptionalExtension, Result as SqlResult, Transaction};
+
+use super::heya::HeyaId;
+use super::leaders::{assign_ord, Rankable};
+use super::{
+    BashoId, BashoRikishi, DataError, FetchBashoRikishi, Player, PlayerId, Rank, Result, RikishiId,
+};
+
+pub const PICK_MINUTES: RangeInclusive<i64> = 1..=24 * 60;
+/// Each member drafts this many rikishi, unless the banzuke runs out first.
+pub const DRAFT_ROUNDS: usize = 5;
+
+pub struct DraftPick {
+    pub number: usize,
+    pub player_id: PlayerId,
+    pub rikishi_id: RikishiId,
+    pub name: String,
+    pub rank: Rank,
+    pub is_auto: bool,
+}
+
+/// A heya's draft for one basho.
+pub struct HeyaDraft {
+    pub heya_id: HeyaId,
+    pub basho_id: BashoId,
+    pub pick_minutes: i64,
+    pub rounds: usize,
+    pub turn_start_date: DateTime<Utc>,
+    /// Members in first-round draft order.
+    pub order: Vec<Player>,
+    /// In draft order.
+    pub picks: Vec<DraftPick>,
+}
+
+/// Which position in the first-round order makes pick `number`. The order reverses every round so nobody always picks last.
+fn snake_position(number: usize, members: usize) -> usize {
+    let (round, index) = (number / members, number % members);
+    if round % 2 == 0 {
+        index
+    } else {
+        members - 1 - index
+    }
+}
+
+impl HeyaDraft {
+    pub fn for_heya(db: &Connection, heya_id: HeyaId, basho_id: BashoId) -> Result<Option<Self>> {
+        let Some((pick_minutes, rounds, turn_start_date)) = db
+            .query_row(
+                "
+                    SELECT pick_minutes, rounds, turn_start_date
+                    FROM heya_draft
+                    WHERE heya_id = ? AND basho_id = ?
+                ",
+                params![heya_id, basho_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        let order = db
+            .prepare(
+                "
+                    SELECT p.*
+                    FROM heya_draft_member AS dm
+                    JOIN player_info AS p ON p.id = dm.player_id
+                    WHERE dm.heya_id = ? AND dm.basho_id = ?
+                    ORDER BY dm.position
+                ",
+            )?
+            .query_map(params![heya_id, basho_id], Player::from_row)?
+            .collect::<SqlResult<_>>()?;
+        let picks = db
+            .prepare(
+                "
+                    SELECT dp.pick_number, dp.player_id, dp.rikishi_id, b.family_name, b.rank, dp.is_auto
+                    FROM heya_draft_pick AS dp
+                    JOIN banzuke AS b ON b.rikishi_id = dp.rikishi_id AND b.basho_id = dp.basho_id
+                    WHERE dp.heya_id = ? AND dp.basho_id = ?
+                    ORDER BY dp.pick_number
+                ",
+            )?
+            .query_map(params![heya_id, basho_id], |row| {
+                Ok(DraftPick {
+                    number: row.get(0)?,
+                    player_id: row.get(1)?,
+                    rikishi_id: row.get(2)?,
+                    name: row.get(3)?,
+                    rank: row.get(4)?,
+                    is_auto: row.get(5)?,
+                })
+            })?
+            .collect::<SqlResult<_>>()?;
+        Ok(Some(Self {
+            heya_id,
+            basho_id,
+            pick_minutes,
+            rounds,
+            turn_start_date,
+            order,
+            picks,
+        }))
+    }
+
+    /// Fixes the draft order by shuffling the current members, and puts the first of them on the clock.
+    pub fn start(
+        db: &mut Connection,
+        heya_id: HeyaId,
+        basho_id: BashoId,
+        pick_minutes: i64,
+    ) -> Result<()> {
+        if !PICK_MINUTES.contains(&pick_minutes) {
+            return Err(DataError::HeyaIntegrity {
+                what: format!(
+                    "Pick timer must be between {} and {} minutes",
+                    PICK_MINUTES.start(),
+                    PICK_MINUTES.end()
+                ),
+            });
+        }
+        let txn = db.transaction()?;
+        let start_date: DateTime<Utc> = txn.query_row(
+            "SELECT start_date FROM basho WHERE id = ?",
+            params![basho_id],
+            |row| row.get(0),
+        )?;
+        let now = Utc::now();
+        if start_date < now {
+            return Err(DataError::BashoHasStarted);
+        }
+        if Self::for_heya(&txn, heya_id, basho_id)?.is_some() {
+            return Err(DataError::HeyaIntegrity {
+                what: "The draft has already started".to_string(),
+            });
+        }
+
+        let members: Vec<PlayerId> = txn
+            .prepare("SELECT player_id FROM heya_player WHERE heya_id = ?")?
+            .query_map(params![heya_id], |row| row.get(0))?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .sorted_by_cached_key(|_| random::<u64>())
+            .collect();
+        let available: usize = txn.query_row(
+            "SELECT COUNT(*) FROM banzuke WHERE basho_id = ? AND NOT kyujyo",
+            params![basho_id],
+            |row| row.get(0),
+        )?;
+        let rounds = DRAFT_ROUNDS.min(available / members.len().max(1));
+        if members.len() < 2 || rounds == 0 {
+            return Err(DataError::HeyaIntegrity {
+                what: "A draft needs at least two members and a banzuke".to_string(),
+            });
+        }
+
+        txn.execute(
+            "
+                INSERT INTO heya_draft (heya_id, basho_id, pick_minutes, rounds, create_date, turn_start_date)
+                VALUES (?, ?, ?, ?, ?, ?)
+            ",
+            params![heya_id, basho_id, pick_minutes, rounds, now, now],
+        )?;
+        for (position, player_id) in members.iter().enumerate() {
+            txn.execute(
+                "
+                    INSERT INTO heya_draft_member (heya_id, basho_id, player_id, position)
+                    VALUES (?, ?, ?, ?)
+                ",
+                params![heya_id, basho_id, player_id, position],
+            )?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    pub fn pick_count(&self) -> usize {
+        self.order.len() * self.rounds
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.picks.len() >= self.pick_count()
+    }
+
+    /// The member who makes pick `number`.
+    pub fn drafter(&self, number: usize) -> &Player {
+        &self.order[snake_position(number, self.order.len())]
+    }
+
+    pub fn on_the_clock(&self) -> Option<&Player> {
+        if self.is_complete() {
+            None
+        } else {
+            Some(self.drafter(self.picks.len()))
+        }
+    }
+
+    pub fn turn_deadline(&self) -> DateTime<Utc> {
+        self.turn_start_date + TimeDelta::minutes(self.pick_minutes)
+    }
+
+    /// Whether the member on the clock ran out of time and is waiting to be auto-drafted for by `auto_draft_expired`.
+    pub fn is_turn_expired(&self) -> bool {
+        !self.is_complete() && self.turn_deadline() <= Utc::now()
+    }
+
+    pub fn round(&self, pick: &DraftPick) -> usize {
+        pick.number / self.order.len() + 1
+    }
+
+    pub fn picks_for(&self, player_id: PlayerId) -> Vec<&DraftPick> {
+        self.picks
+            .iter()
+            .filter(|p| p.player_id == player_id)
+            .collect()
+    }
+
+    /// Drafts a rikishi for the member on the clock.
+    pub fn make_pick(
+        db: &mut Connection,
+        heya_id: HeyaId,
+        basho_id: BashoId,
+        player_id: PlayerId,
+        rikishi_id: RikishiId,
+    ) -> Result<()> {
+        let txn = db.transaction()?;
+        let start_date: DateTime<Utc> = txn.query_row(
+            "SELECT start_date FROM basho WHERE id = ?",
+            params![basho_id],
+            |row| row.get(0),
+        )?;
+        if start_date < Utc::now() {
+            return Err(DataError::BashoHasStarted);
+        }
+        Self::auto_draft_expired(&txn, heya_id, basho_id, false)?;
+        let draft =
+            Self::for_heya(&txn, heya_id, basho_id)?.ok_or_else(|| DataError::HeyaIntegrity {
+                what: "The draft hasn’t started".to_string(),
+            })?;
+        if draft.on_the_clock().map(|p| p.id) != Some(player_id) {
+            return Err(DataError::HeyaIntegrity {
+                what: "It isn’t your turn to draft".to_string(),
+            });
+        }
+        let rikishi = FetchBashoRikishi::with_db(&txn, basho_id, &HashSet::new())?.by_id;
+        let is_available = rikishi.get(&rikishi_id).is_some_and(|r| !r.is_kyujyo)
+            && !draft.picks.iter().any(|p| p.rikishi_id == rikishi_id);
+        if !is_available {
+            return Err(DataError::InvalidPicks);
+        }
+        draft.insert_pick(&txn, rikishi_id, Utc::now(), false)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn insert_pick(
+        &self,
+        txn: &Transaction,
+        rikishi_id: RikishiId,
+        pick_date: DateTime<Utc>,
+        is_auto: bool,
+    ) -> Result<()> {
+        let number = self.picks.len();
+        txn.execute(
+            "
+                INSERT INTO heya_draft_pick (heya_id, basho_id, pick_number, player_id, rikishi_id, pick_date, is_auto)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+            ",
+            params![
+                self.heya_id,
+                self.basho_id,
+                number,
+                self.drafter(number).id,
+                rikishi_id,
+                pick_date,
+                is_auto
+            ],
+        )?;
+        // The next member's clock starts when this pick is made.
+        txn.execute(
+            "UPDATE heya_draft SET turn_start_date = ? WHERE heya_id = ? AND basho_id = ?",
+            params![pick_date, self.heya_id, self.basho_id],
+        )?;
+        Ok(())
+    }
+
+    /// Drafts the highest-ranked available rikishi for every turn whose timer has run out, or for every remaining turn with `finish`. Each expired turn starts the next one's clock from its deadline, so catching up after a quiet spell gives the same picks as if they were made on time. Returns how many picks were made.
+    pub fn auto_draft_expired(
+        txn: &Transaction,
+        heya_id: HeyaId,
+        basho_id: BashoId,
+        finish: bool,
+    ) -> Result<usize> {
+        let Some(mut draft) = Self::for_heya(txn, heya_id, basho_id)? else {
+            return Ok(0);
+        };
+        let now = Utc::now();
+        if draft.is_complete() || (!finish && draft.turn_deadline() > now) {
+            return Ok(0);
+        }
+        let mut available: Vec<BashoRikishi> =
+            FetchBashoRikishi::with_db(txn, basho_id, &HashSet::new())?
+                .by_id
+                .into_values()
+                .filter(|r| !r.is_kyujyo && !draft.picks.iter().any(|p| p.rikishi_id == r.id))
+                .sorted_by_key(|r| r.rank)
+                .rev()
+                .collect();
+        let mut count = 0;
+        while !draft.is_complete() && (finish || draft.turn_deadline() <= now) {
+            let Some(rikishi) = available.pop() else {
+                break;
+            };
+            let pick_date = draft.turn_deadline().min(now);
+            let number = draft.picks.len();
+            let player_id = draft.drafter(number).id;
+            debug!(
+                "auto-drafting {} for player {} in heya {} {}",
+                rikishi.name, player_id, heya_id, basho_id
+            );
+            draft.insert_pick(txn, rikishi.id, pick_date, true)?;
+            draft.picks.push(DraftPick {
+                number,
+                player_id,
+                rikishi_id: rikishi.id,
+                name: rikishi.name,
+                rank: rikishi.rank,
+                is_auto: true,
+            });
+            draft.turn_start_date = pick_date;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Finishes every draft for a basho once it has started.
+    pub fn finish_all(txn: &Transaction, basho_id: BashoId) -> Result<usize> {
+        let heya_ids: Vec<HeyaId> = txn
+            .prepare("SELECT heya_id FROM heya_draft WHERE basho_id = ?")?
+            .query_map(params![basho_id], |row| row.get(0))?
+            .collect::<SqlResult<_>>()?;
+        let mut count = 0;
+        for heya_id in heya_ids {
+            count += Self::auto_draft_expired(txn, heya_id, basho_id, true)?;
+        }
+        Ok(count)
+    }
+}
+
+/// A member's place on the heya's draft leaderboard, scored by their drafted rikishi's wins.
+pub struct DraftStanding {
+    pub ord: usize,
+    pub player: Player,
+    pub wins: u32,
+}
+
+impl Rankable for DraftStanding {
+    fn get_score(&self) -> i32 {
+        self.wins as i32
+    }
+
+    fn set_rank(&mut self, ord: usize) {
+        self.ord = ord;
+    }
+}
+
+impl DraftStanding {
+    pub fn for_draft(db: &Connection, heya_id: HeyaId, basho_id: BashoId) -> Result<Vec<Self>> {
+        let mut standings = db
+            .prepare(
+                "
+                    SELECT
+                        player.*,
+                        COALESCE(SUM(t.win), 0) AS wins
+                    FROM heya_draft_member AS dm
+                    JOIN player_info AS player ON player.id = dm.player_id
+                    LEFT JOIN heya_draft_pick AS dp
+                        ON dp.heya_id = dm.heya_id AND dp.basho_id = dm.basho_id AND dp.player_id = dm.player_id
+                    LEFT JOIN torikumi AS t ON t.basho_id = dp.basho_id AND t.rikishi_id = dp.rikishi_id
+                    WHERE dm.heya_id = ? AND dm.basho_id = ?
+                    GROUP BY player.id
+                    ORDER BY wins DESC, dm.position
+                ",
+            )?
+            .query_map(params![heya_id, basho_id], |row| {
+                Ok(Self {
+                    ord: 0,
+                    player: Player::from_row(row)?,
+                    wins: row.get("wins")?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        assign_ord(&mut standings.iter_mut());
+        Ok(standings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_order_reverses_each_round() {
+        let order: Vec<usize> = (0..9).map(|n| snake_position(n, 3)).collect();
+        assert_eq!(order, [0, 1, 2, 2, 1, 0, 0, 1, 2]);
+    }
+}
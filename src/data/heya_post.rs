@@ -0,0 +1,198 @@
+use std::ops::RangeInclusive;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use rusqlite::{Connection, OptionalExtension, Result as SqlResult, Row};
+
+use super::heya::HeyaId;
+use super::{BashoId, DataError, Player, PlayerId, Result};
+
+pub const POST_LENGTH: RangeInclusive<usize> = 1..=500;
+pub const POST_RATE_MAX: usize = 5;
+pub const POST_RATE_MINUTES: i64 = 10;
+
+pub type HeyaPostId = i64;
+
+/// A message on a heya's board. Posts with a `basho_id` belong to that basho's thread; the rest are in the general thread.
+#[derive(Debug)]
+pub struct HeyaPost {
+    pub id: HeyaPostId,
+    pub heya_id: HeyaId,
+    pub player: Player,
+    pub basho_id: Option<BashoId>,
+    pub body: String,
+    pub post_date: DateTime<Utc>,
+    pub is_pinned: bool,
+}
+
+impl HeyaPost {
+    fn from_row(row: &Row) -> SqlResult<Self> {
+        Ok(Self {
+            id: row.get("post_id")?,
+            heya_id: row.get("heya_id")?,
+            player: Player::from_row(row)?,
+            basho_id: row.get("basho_id")?,
+            body: row.get("body")?,
+            post_date: row.get("post_date")?,
+            is_pinned: row.get("is_pinned")?,
+        })
+    }
+
+    pub fn with_id(db: &Connection, id: HeyaPostId) -> Result<Option<Self>> {
+        Ok(db
+            .query_row(
+                "
+                    SELECT
+                        post.id AS post_id,
+                        post.heya_id,
+                        post.basho_id,
+                        post.body,
+                        post.post_date,
+                        post.is_pinned,
+                        p.*
+                    FROM heya_post AS post
+                    JOIN player_info AS p ON p.id = post.player_id
+                    WHERE post.id = ?
+                ",
+                params![id],
+                Self::from_row,
+            )
+            .optional()?)
+    }
+
+    /// Pinned posts first, then newest first.
+    pub fn in_thread(
+        db: &Connection,
+        heya_id: HeyaId,
+        basho_id: Option<BashoId>,
+    ) -> Result<Vec<Self>> {
+        Ok(db
+            .prepare(
+                "
+                    SELECT
+                        post.id AS post_id,
+                        post.heya_id,
+                        post.basho_id,
+                        post.body,
+                        post.post_date,
+                        post.is_pinned,
+                        p.*
+                    FROM heya_post AS post
+                    JOIN player_info AS p ON p.id = post.player_id
+                    WHERE post.heya_id = ? AND post.basho_id IS ?
+                    ORDER BY post.is_pinned DESC, post.post_date DESC
+                ",
+            )?
+            .query_map(params![heya_id, basho_id], Self::from_row)?
+            .collect::<SqlResult<_>>()?)
+    }
+
+    /// Basho threads that have at least one post, newest first.
+    pub fn basho_threads(db: &Connection, heya_id: HeyaId) -> Result<Vec<BashoId>> {
+        Ok(db
+            .prepare(
+                "
+                    SELECT DISTINCT basho_id
+                    FROM heya_post
+                    WHERE heya_id = ? AND basho_id IS NOT NULL
+                    ORDER BY basho_id DESC
+                ",
+            )?
+            .query_map(params![heya_id], |row| row.get(0))?
+            .collect::<SqlResult<_>>()?)
+    }
+
+    pub fn create(
+        db: &mut Connection,
+        heya_id: HeyaId,
+        player: PlayerId,
+        basho_id: Option<BashoId>,
+        body: &str,
+    ) -> Result<HeyaPostId> {
+        let body = body.trim();
+        Self::validate_body(body)?;
+        let txn = db.transaction()?;
+        Self::validate_rate(&txn, heya_id, player)?;
+        txn.prepare(
+            "
+                INSERT INTO heya_post (heya_id, player_id, basho_id, body, post_date)
+                VALUES (?, ?, ?, ?, ?)
+            ",
+        )?
+        .execute(params![heya_id, player, basho_id, body, Utc::now()])?;
+        let id = txn.last_insert_rowid();
+        txn.commit()?;
+        Ok(id)
+    }
+
+    pub fn set_pinned(&mut self, db: &Connection, is_pinned: bool) -> Result<()> {
+        db.prepare(
+            "
+                UPDATE heya_post SET is_pinned = ? WHERE id = ?
+            ",
+        )?
+        .execute(params![is_pinned, self.id])?;
+        self.is_pinned = is_pinned;
+        Ok(())
+    }
+
+    pub fn delete(self, db: &Connection) -> Result<()> {
+        db.prepare(
+            "
+                DELETE FROM heya_post WHERE id = ?
+            ",
+        )?
+        .execute(params![self.id])?;
+        Ok(())
+    }
+
+    pub fn url_path(&self, heya_slug: &str) -> String {
+        match self.basho_id {
+            Some(basho_id) => format!("/heya/{}?thread={}#board", heya_slug, basho_id.id()),
+            None => format!("/heya/{}#board", heya_slug),
+        }
+    }
+
+    fn validate_body(body: &str) -> Result<()> {
+        if POST_LENGTH.contains(&body.chars().count()) {
+            Ok(())
+        } else {
+            Err(DataError::HeyaIntegrity {
+                what: format!(
+                    "Message must be {} to {} characters",
+                    POST_LENGTH.start(),
+                    POST_LENGTH.end()
+                ),
+            })
+        }
+    }
+
+    fn validate_rate(db: &Connection, heya_id: HeyaId, player: PlayerId) -> Result<()> {
+        let since = Utc::now() - TimeDelta::minutes(POST_RATE_MINUTES);
+        let recent = db
+            .prepare(
+                "
+                    SELECT post_date FROM heya_post
+                    WHERE heya_id = ? AND player_id = ?
+                    ORDER BY post_date DESC
+                    LIMIT ?
+                ",
+            )?
+            .query_map(params![heya_id, player, POST_RATE_MAX as u32], |row| {
+                row.get::<_, DateTime<Utc>>(0)
+            })?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .filter(|date| *date > since)
+            .count();
+        if recent < POST_RATE_MAX {
+            Ok(())
+        } else {
+            Err(DataError::HeyaIntegrity {
+                what: format!(
+                    "Too many messages; you can post {} every {} minutes",
+                    POST_RATE_MAX, POST_RATE_MINUTES
+                ),
+            })
+        }
+    }
+}
@@ -1,22 +1,49 @@
 use rusqlite::{Connection, Result as SqlResult};
-use std::{collections::HashMap, ops::Range};
+use std::cmp::Ordering;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
+use super::promotion::RankReason;
+use super::simulation::SimulatedOdds;
 use super::{
-    heya::HeyaId, BashoId, BashoRikishi, Player, PlayerId, Rank, RankName, RankSide, Result,
-    RikishiId,
+    heya::HeyaId, BashoId, BashoInfo, BashoRikishi, Player, PlayerId, Rank, RankName, RankSide,
+    Result, RikishiId,
 };
-use crate::util::GroupRuns;
 use std::sync::Arc;
 
 pub struct BashoPlayerResults {
     pub player: ResultPlayer,
     pub total: u8,
     pub days: [Option<u8>; 15],
+    /// Highest total still attainable if every non-kyujyo pick wins all their remaining bouts.
+    pub max_score: u8,
+    /// Whether `max_score` can no longer reach the basho leader's current total.
+    pub is_eliminated: bool,
+    /// Simulated chances of winning, from the latest day simulated.
+    pub odds: Option<SimulatedOdds>,
+    /// Whether the player's yusho pick matched the recorded winner.
+    pub yusho_correct: bool,
     picks: [Option<RikishiId>; 5],
+    /// Which of `picks` were filled by `auto_pick::fill_auto_picks`.
+    auto_picks: [bool; 5],
+    /// Index into `picks` of the captain, whose wins count double.
+    captain: Option<usize>,
     rikishi_by_id: Arc<HashMap<RikishiId, BashoRikishi>>,
     pub is_self: bool,
 }
 
+struct LeaderRow(
+    Player,
+    u8,
+    u32,
+    String,
+    Option<String>,
+    Option<RikishiId>,
+    bool,
+);
+
 #[allow(clippy::large_enum_variant)]
 pub enum ResultPlayer {
     RankedPlayer(Player, usize),
@@ -33,6 +60,14 @@ impl BashoPlayerResults {
             .collect()
     }
 
+    pub fn is_auto_pick(&self, index: usize) -> bool {
+        self.auto_picks.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn is_captain_pick(&self, index: usize) -> bool {
+        self.captain == Some(index)
+    }
+
     fn sort_key_during_basho(&self) -> impl Ord {
         match &self.player {
             ResultPlayer::RankedPlayer(_, basho_rank) => (
@@ -71,6 +106,7 @@ impl BashoPlayerResults {
         );
 
         let rikishi = Arc::new(rikishi);
+        let picks_are_sealed = BashoInfo::picks_are_sealed_for_id(db, basho_id)?;
         let (heya_join, params) = if heya_id.is_some() {
             (
                 "JOIN heya_player AS hp ON hp.player_id = player.id AND hp.heya_id = :heya_id",
@@ -101,12 +137,17 @@ impl BashoPlayerResults {
                         COALESCE(br.wins, 0) AS basho_wins,
                         COALESCE(br.rank, 0) AS basho_rank,
                         player.id = :player_id AS is_self,
-                        GROUP_CONCAT(pick.rikishi_id) AS pick_ids
+                        GROUP_CONCAT(pick.rikishi_id) AS pick_ids,
+                        GROUP_CONCAT(CASE WHEN pick.is_auto THEN pick.rikishi_id END) AS auto_pick_ids,
+                        MAX(CASE WHEN pick.is_captain AND basho.captain_picks THEN pick.rikishi_id END) AS captain_id,
+                        COALESCE(yp.rikishi_id = basho.yusho_rikishi_id, 0) AS yusho_correct
                     FROM pick
                     JOIN player_info AS player ON player.id = pick.player_id
+                    JOIN basho ON basho.id = pick.basho_id
                     {heya_join}
                     LEFT JOIN player_rank AS pr ON pr.player_id = player.id AND pr.before_basho_id = pick.basho_id
                     LEFT JOIN basho_result AS br USING (player_id, basho_id)
+                    LEFT JOIN yusho_pick AS yp ON yp.player_id = player.id AND yp.basho_id = pick.basho_id
                     WHERE pick.basho_id = :basho_id
                     GROUP BY player.id
                     ORDER BY is_self DESC, basho_wins DESC, player.id ASC
@@ -116,29 +157,50 @@ impl BashoPlayerResults {
             .unwrap()
             .query_map(
                params,
-                |row| -> SqlResult<(Player, u8, u32, String)> {
-                    Ok((
+                |row| -> SqlResult<LeaderRow> {
+                    Ok(LeaderRow(
                         Player::from_row(row)?,
                         row.get("basho_wins")?,
                         row.get("basho_rank")?,
                         row.get("pick_ids")?,
+                        row.get("auto_pick_ids")?,
+                        row.get("captain_id")?,
+                        row.get("yusho_correct")?,
                     ))
                 },
             )?
-            .collect::<SqlResult<Vec<(Player, u8, u32, String)>>>()?
+            .collect::<SqlResult<Vec<LeaderRow>>>()?
             .into_iter()
-            .map(|(player, total, rank, picks_str)| {
+            .map(|LeaderRow(player, total, rank, picks_str, auto_picks_str, captain_id, yusho_correct)| {
+                let auto_pick_ids: HashSet<RikishiId> = auto_picks_str
+                    .iter()
+                    .flat_map(|s| s.split(','))
+                    .map(|id| id.parse().unwrap())
+                    .collect();
                 let mut picks = [None; 5];
+                let mut auto_picks = [false; 5];
                 let mut pick_rikishi = [None; 5];
+                let mut captain = None;
                 for r in picks_str
                     .split(',')
                     .filter_map(|id| rikishi.get(&id.parse().unwrap()))
                 {
                     let group = r.rank.group().as_index();
                     picks[group] = Some(r.id);
+                    auto_picks[group] = auto_pick_ids.contains(&r.id);
                     pick_rikishi[group] = Some(r);
+                    if captain_id == Some(r.id) {
+                        captain = Some(group);
+                    }
+                }
+                if picks_are_sealed && player_id != Some(player.id) {
+                    picks = [None; 5];
+                    auto_picks = [false; 5];
+                    pick_rikishi = [None; 5];
+                    captain = None;
                 }
-                let (days, total_validation) = picks_to_days(&pick_rikishi);
+                let (days, total_validation) = picks_to_days(&pick_rikishi, captain);
+                let max_score = max_attainable(&pick_rikishi, captain);
                 if total != total_validation {
                     warn!("total wins for player {} mismatch betwen basho_result {total} and live data {total_validation}", player.name)
                 }
@@ -147,13 +209,31 @@ impl BashoPlayerResults {
                     player: ResultPlayer::RankedPlayer(player, rank as usize),
                     rikishi_by_id: Arc::clone(&rikishi),
                     picks,
+                    auto_picks,
+                    captain,
                     total,
                     days,
+                    max_score,
+                    is_eliminated: false, // set below
+                    odds: None,           // set below
+                    yusho_correct,
                 }
             })
             .collect();
 
         if include_best_worst {
+            let leader_score: u8 = db.query_row(
+                "SELECT COALESCE(MAX(wins), 0) FROM basho_result WHERE basho_id = ?",
+                params![basho_id],
+                |row| row.get(0),
+            )?;
+            let odds = SimulatedOdds::for_basho(db, basho_id)?;
+            for leader in &mut leaders {
+                leader.is_eliminated = leader.max_score < leader_score;
+                if let ResultPlayer::RankedPlayer(player, _) = &leader.player {
+                    leader.odds = odds.get(&player.id).copied();
+                }
+            }
             let (min, max) = make_min_max_results(rikishi);
             leaders.push(min);
             leaders.push(max);
@@ -181,8 +261,10 @@ fn make_min_max_results(
             Some(if r.wins > max.wins { r } else { max })
         });
     }
-    let (min_days, min_total) = picks_to_days(&mins);
-    let (max_days, max_total) = picks_to_days(&maxes);
+    let (min_days, min_total) = picks_to_days(&mins, None);
+    let (max_days, max_total) = picks_to_days(&maxes, None);
+    let min_max_score = max_attainable(&mins, None);
+    let max_max_score = max_attainable(&maxes, None);
     let mut min_ids = [None; 5];
     let mut max_ids = [None; 5];
     for i in 0..5 {
@@ -195,28 +277,61 @@ fn make_min_max_results(
             is_self: false,
             player: ResultPlayer::Min,
             picks: min_ids,
+            auto_picks: [false; 5],
+            captain: None,
             rikishi_by_id: Arc::clone(&rikishi),
             total: min_total,
             days: min_days,
+            max_score: min_max_score,
+            is_eliminated: false,
+            odds: None,
+            yusho_correct: false,
         },
         BashoPlayerResults {
             is_self: false,
             player: ResultPlayer::Max,
             picks: max_ids,
+            auto_picks: [false; 5],
+            captain: None,
             rikishi_by_id: Arc::clone(&rikishi),
             total: max_total,
             days: max_days,
+            max_score: max_max_score,
+            is_eliminated: false,
+            odds: None,
+            yusho_correct: false,
         },
     )
 }
 
-fn picks_to_days(picks: &[Option<&BashoRikishi>; 5]) -> ([Option<u8>; 15], u8) {
+/// How much each pick's wins count: double for the captain.
+fn pick_weight(index: usize, captain: Option<usize>) -> u8 {
+    if captain == Some(index) {
+        2
+    } else {
+        1
+    }
+}
+
+fn max_attainable(picks: &[Option<&BashoRikishi>; 5], captain: Option<usize>) -> u8 {
+    picks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| Some(r.as_ref()?.max_wins() * pick_weight(i, captain)))
+        .sum()
+}
+
+fn picks_to_days(
+    picks: &[Option<&BashoRikishi>; 5],
+    captain: Option<usize>,
+) -> ([Option<u8>; 15], u8) {
     let mut days = [None; 15];
     let mut total_validation = 0;
-    for pick in picks.iter().flatten() {
+    for (i, pick) in picks.iter().enumerate() {
+        let Some(pick) = pick else { continue };
         for (day, win) in pick.results.iter().enumerate() {
             if let Some(win) = win {
-                let incr = if *win { 1 } else { 0 };
+                let incr = if *win { pick_weight(i, captain) } else { 0 };
                 days[day] = Some(days[day].unwrap_or(0) + incr);
                 total_validation += incr;
             }
@@ -229,6 +344,8 @@ pub struct PlayerRanking {
     pub player: Player,
     pub ord: usize,
     pub rank: Rank,
+    pub reason: Option<RankReason>,
+    pub is_kadoban: bool,
     pub wins: u32,
 }
 
@@ -250,6 +367,8 @@ impl PlayerRanking {
             SELECT
                 p.*,
                 pr.rank,
+                pr.reason,
+                pr.is_kadoban,
                 pr.past_year_wins
             FROM player_rank AS pr
             JOIN player_info AS p ON p.id = pr.player_id
@@ -262,20 +381,143 @@ impl PlayerRanking {
                     player: Player::from_row(row)?,
                     ord: 0, // set later
                     rank: row.get("rank")?,
+                    reason: row.get("reason")?,
+                    is_kadoban: row.get("is_kadoban")?,
                     wins: row.get("past_year_wins")?,
                 })
             })?
             .collect::<rusqlite::Result<Vec<Self>>>()?;
+        // ord is by past-year wins but the banzuke itself is in rank order.
         assign_ord(&mut rows.iter_mut());
+        rows.sort_by_key(|r| r.rank);
         Ok(rows)
     }
+
+    pub fn has_banzuke(db: &Connection, before_basho_id: BashoId) -> Result<bool> {
+        Ok(db.query_row(
+            "SELECT EXISTS (SELECT 1 FROM player_rank WHERE before_basho_id = ?)",
+            params![before_basho_id],
+            |row| row.get(0),
+        )?)
+    }
+}
+
+/// Movement of a player's rank compared with the previous basho's banzuke.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RankChange {
+    Promoted,
+    Demoted,
+    Unchanged,
+    New,
+}
+
+impl RankChange {
+    fn between(prev: Option<Rank>, rank: Rank) -> Self {
+        match prev.map(|prev| rank.cmp(&prev)) {
+            None => Self::New,
+            Some(Ordering::Less) => Self::Promoted,
+            Some(Ordering::Greater) => Self::Demoted,
+            Some(Ordering::Equal) => Self::Unchanged,
+        }
+    }
+
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Self::Promoted => "↑",
+            Self::Demoted => "↓",
+            Self::Unchanged => "→",
+            Self::New => "★",
+        }
+    }
+
+    pub fn css_class(self) -> &'static str {
+        match self {
+            Self::Promoted => "promoted",
+            Self::Demoted => "demoted",
+            Self::Unchanged => "unchanged",
+            Self::New => "new",
+        }
+    }
+}
+
+pub struct BanzukeEntry {
+    pub ranking: PlayerRanking,
+    pub prev_rank: Option<Rank>,
+    pub change: RankChange,
+}
+
+impl BanzukeEntry {
+    pub fn title(&self) -> String {
+        let prev = match self.prev_rank {
+            Some(prev) => format!("Previously {prev:#}"),
+            None => "New to the banzuke".to_string(),
+        };
+        match self.ranking.reason {
+            Some(reason) => format!("{prev}. {}", reason.description()),
+            None => prev,
+        }
+    }
+}
+
+/// One line of the banzuke. Ranks from before banzuke-style promotion could be shared, so each side may hold several players.
+pub struct BanzukeRow {
+    pub number: u16,
+    pub east: Vec<BanzukeEntry>,
+    pub west: Vec<BanzukeEntry>,
+}
+
+pub struct BanzukeRankGroup {
+    pub name: RankName,
+    pub rows: Vec<BanzukeRow>,
+}
+
+impl BanzukeRankGroup {
+    /// The full player banzuke for a basho, grouped by rank name and then east/west rows, with each player's movement since the previous basho.
+    pub fn for_basho(db: &Connection, before_basho_id: BashoId) -> Result<Vec<Self>> {
+        let prev_ranks: HashMap<PlayerId, Rank> = db
+            .prepare("SELECT player_id, rank FROM player_rank WHERE before_basho_id = ?")?
+            .query_map(params![before_basho_id.incr(-1)], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<SqlResult<_>>()?;
+
+        let mut groups: Vec<Self> = vec![];
+        for ranking in PlayerRanking::for_home_page(db, before_basho_id)? {
+            let rank = ranking.rank;
+            let prev_rank = prev_ranks.get(&ranking.player.id).copied();
+            let entry = BanzukeEntry {
+                change: RankChange::between(prev_rank, rank),
+                prev_rank,
+                ranking,
+            };
+            if groups.last().is_none_or(|g| g.name != rank.name) {
+                groups.push(Self {
+                    name: rank.name,
+                    rows: vec![],
+                });
+            }
+            let rows = &mut groups.last_mut().unwrap().rows;
+            if rows.last().is_none_or(|r| r.number != rank.number) {
+                rows.push(BanzukeRow {
+                    number: rank.number,
+                    east: vec![],
+                    west: vec![],
+                });
+            }
+            let row = rows.last_mut().unwrap();
+            match rank.side {
+                RankSide::East => row.east.push(entry),
+                RankSide::West => row.west.push(entry),
+            }
+        }
+        Ok(groups)
+    }
 }
 
 #[derive(Debug)]
 pub struct HistoricLeader {
     pub player: Player,
     pub ord: usize,
-    pub rank: Rank,
     pub wins: NumericStats,
     pub ranks: NumericStats,
 }
@@ -336,7 +578,6 @@ impl HistoricLeader {
                 |row| Ok(Self {
                     player: Player::from_row(row)?,
                     ord: 0,
-                    rank: Rank::top(),
                     wins: NumericStats {
                         total: row.get("total_wins")?,
                         min: row.get("min_wins")?,
@@ -353,23 +594,8 @@ impl HistoricLeader {
             )?
             .collect::<Result<Vec<Self>>>()?;
         assign_ord(&mut leaders.iter_mut());
-        Self::assign_rank(&mut leaders);
         Ok(leaders)
     }
-
-    fn assign_rank(leaders: &mut [HistoricLeader]) {
-        let mut rank = Rank {
-            name: RankName::Yokozuna,
-            side: RankSide::East,
-            number: 1,
-        };
-        for group in leaders.group_runs_mut(|a, b| a.ord == b.ord) {
-            for leader in group.iter_mut() {
-                leader.rank = rank;
-            }
-            rank = rank.next_lower();
-        }
-    }
 }
 
 pub trait Rankable {
@@ -31,6 +31,31 @@ pub mod push;
 pub mod heya;
 pub use heya::Heya;
 
+pub mod heya_post;
+
+pub mod player_stats;
+
+pub mod rating;
+
+pub mod promotion;
+
+pub mod simulation;
+
+pub mod auto_pick;
+
+pub mod pick_history;
+
+pub mod bout_prediction;
+
+pub mod heya_draft;
+
+pub mod banzuke_prediction;
+
+pub mod over_under;
+
+#[cfg(test)]
+pub(crate) mod testing;
+
 pub type RikishiId = u32;
 pub type Day = u8;
 
@@ -70,6 +95,8 @@ type Result<T> = std::result::Result<T, DataError>;
 #[derive(Debug)]
 pub enum DataError {
     BashoHasStarted,
+    DayHasStarted,
+    PredictionsClosed,
     InvalidPicks,
     HeyaIntegrity {
         what: String,
@@ -115,6 +142,8 @@ impl fmt::Display for DataError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DataError::BashoHasStarted => write!(f, "Basho has already started"),
+            DataError::DayHasStarted => write!(f, "This day's bouts have already started"),
+            DataError::PredictionsClosed => write!(f, "Predictions are closed"),
             DataError::InvalidPicks => write!(f, "Invalid picks"),
             DataError::HeyaIntegrity { what } => write!(f, "Heya integrity error: {}", what),
             DataError::RikishiNotFound { family_name } => {
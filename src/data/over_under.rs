@@ -0,0 +1,324 @@
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use rusqlite::{Connection, Result as SqlResult, Transaction};
+use std::collections::HashSet;
+
+use super::leaders::{assign_ord, Rankable};
+use super::{BashoId, DataError, Player, PlayerId, Rank, RankName, Result, RikishiId};
+
+/// Lines are stored as whole numbers and shown with a half win added, so they stay within these bounds.
+pub const LINES: std::ops::RangeInclusive<u8> = 0..=14;
+
+/// The line for a rikishi at `rank`, nudged by a third of how far last basho's wins were from kachi-koshi.
+pub fn default_line(rank: Rank, last_wins: Option<u8>) -> u8 {
+    let base: i32 = match rank.name {
+        RankName::Yokozuna => 11,
+        RankName::Ozeki => 9,
+        RankName::Sekiwake => 8,
+        _ => 7,
+    };
+    let form = last_wins.map_or(0, |wins| (wins as i32 - 8) / 3);
+    (base + form).clamp(3, 12) as u8
+}
+
+/// A rikishi featured in the over/under game, with the viewing player's pick.
+pub struct FeaturedRikishi {
+    pub id: RikishiId,
+    pub name: String,
+    pub rank: Rank,
+    pub line: u8,
+    pub wins: u8,
+    pub pick: Option<bool>,
+    pub is_correct: Option<bool>,
+}
+
+impl FeaturedRikishi {
+    pub fn for_basho(
+        db: &Connection,
+        basho_id: BashoId,
+        player_id: Option<PlayerId>,
+    ) -> Result<Vec<Self>> {
+        let featured = db
+            .prepare(
+                "
+                SELECT
+                    b.rikishi_id,
+                    b.family_name,
+                    b.rank,
+                    wl.line,
+                    (
+                        SELECT COALESCE(SUM(t.win), 0)
+                        FROM torikumi AS t
+                        WHERE t.basho_id = wl.basho_id AND t.rikishi_id = wl.rikishi_id
+                    ) AS wins,
+                    p.is_over,
+                    p.is_correct
+                FROM win_line AS wl
+                JOIN banzuke AS b ON b.basho_id = wl.basho_id AND b.rikishi_id = wl.rikishi_id
+                LEFT JOIN win_line_pick AS p
+                    ON p.basho_id = wl.basho_id AND p.rikishi_id = wl.rikishi_id AND p.player_id = :player_id
+                WHERE wl.basho_id = :basho_id
+            ",
+            )?
+            .query_map(
+                named_params! {":basho_id": basho_id, ":player_id": player_id},
+                |row| {
+                    Ok(Self {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        rank: row.get(2)?,
+                        line: row.get(3)?,
+                        wins: row.get(4)?,
+                        pick: row.get(5)?,
+                        is_correct: row.get(6)?,
+                    })
+                },
+            )?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .sorted_by_key(|f| f.rank)
+            .collect();
+        Ok(featured)
+    }
+}
+
+/// Lines and picks are locked once the basho starts, so every pick is scored against the line it was made on.
+fn check_not_started(db: &Connection, basho_id: BashoId) -> Result<()> {
+    let start_date: DateTime<Utc> = db.query_row(
+        "SELECT start_date FROM basho WHERE id = ?",
+        params![basho_id],
+        |row| row.get(0),
+    )?;
+    if start_date < Utc::now() {
+        return Err(DataError::BashoHasStarted);
+    }
+    Ok(())
+}
+
+/// Features every non-kyujyo rikishi ranked komusubi or higher, each at their default line. Lines already set are left alone.
+pub fn feature_defaults(db: &mut Connection, basho_id: BashoId) -> Result<usize> {
+    let txn = db.transaction()?;
+    check_not_started(&txn, basho_id)?;
+    let rikishi: Vec<(RikishiId, Rank, Option<u8>)> = txn
+        .prepare(
+            "
+            SELECT
+                b.rikishi_id,
+                b.rank,
+                (
+                    SELECT SUM(t.win)
+                    FROM torikumi AS t
+                    WHERE t.basho_id = :last_basho_id AND t.rikishi_id = b.rikishi_id
+                ) AS last_wins
+            FROM banzuke AS b
+            WHERE b.basho_id = :basho_id AND NOT b.kyujyo
+        ",
+        )?
+        .query_map(
+            named_params! {":basho_id": basho_id, ":last_basho_id": basho_id.incr(-1)},
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?
+        .collect::<SqlResult<_>>()?;
+    let mut count = 0;
+    for (rikishi_id, rank, last_wins) in rikishi {
+        if rank.name <= RankName::Komusubi {
+            count += txn.execute(
+                "
+                    INSERT INTO win_line (basho_id, rikishi_id, line)
+                    VALUES (?, ?, ?)
+                    ON CONFLICT DO NOTHING
+                ",
+                params![basho_id, rikishi_id, default_line(rank, last_wins)],
+            )?;
+        }
+    }
+    txn.commit()?;
+    Ok(count)
+}
+
+/// Features a rikishi at the given line, or removes them and any picks on them when `line` is none. Only allowed before the basho starts.
+pub fn set_line(
+    db: &Connection,
+    basho_id: BashoId,
+    rikishi_id: RikishiId,
+    line: Option<u8>,
+) -> Result<()> {
+    check_not_started(db, basho_id)?;
+    match line {
+        Some(line) if LINES.contains(&line) => {
+            db.execute(
+                "
+                    INSERT INTO win_line (basho_id, rikishi_id, line)
+                    VALUES (?, ?, ?)
+                    ON CONFLICT (basho_id, rikishi_id) DO UPDATE SET line = excluded.line
+                ",
+                params![basho_id, rikishi_id, line],
+            )?;
+        }
+        Some(_) => return Err(DataError::InvalidPicks),
+        None => {
+            db.execute(
+                "DELETE FROM win_line WHERE basho_id = ? AND rikishi_id = ?",
+                params![basho_id, rikishi_id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the player's over/under picks, which close when the basho starts. `picks` pairs each featured rikishi with whether the player takes the over.
+pub fn save_picks(
+    db: &mut Connection,
+    player_id: PlayerId,
+    basho_id: BashoId,
+    picks: &[(RikishiId, bool)],
+) -> Result<()> {
+    let txn = db.transaction()?;
+    check_not_started(&txn, basho_id)?;
+    let featured: HashSet<RikishiId> = txn
+        .prepare("SELECT rikishi_id FROM win_line WHERE basho_id = ?")?
+        .query_map(params![basho_id], |row| row.get(0))?
+        .collect::<SqlResult<_>>()?;
+    if !picks.iter().all(|(id, _)| featured.contains(id)) {
+        return Err(DataError::InvalidPicks);
+    }
+    txn.execute(
+        "DELETE FROM win_line_pick WHERE player_id = ? AND basho_id = ?",
+        params![player_id, basho_id],
+    )?;
+    for (rikishi_id, is_over) in picks {
+        txn.execute(
+            "
+                INSERT INTO win_line_pick (player_id, basho_id, rikishi_id, is_over)
+                VALUES (?, ?, ?, ?)
+            ",
+            params![player_id, basho_id, rikishi_id, is_over],
+        )?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+/// Settles every pick against the featured rikishi's final win totals from `torikumi`.
+pub fn resolve(txn: &Transaction, basho_id: BashoId) -> Result<()> {
+    let results: Vec<(RikishiId, bool)> = txn
+        .prepare(
+            "
+            SELECT wl.rikishi_id, COALESCE(SUM(t.win), 0) > wl.line AS went_over
+            FROM win_line AS wl
+            LEFT JOIN torikumi AS t ON t.basho_id = wl.basho_id AND t.rikishi_id = wl.rikishi_id
+            WHERE wl.basho_id = ?
+            GROUP BY wl.rikishi_id
+        ",
+        )?
+        .query_map(params![basho_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<SqlResult<_>>()?;
+    let mut stmt = txn.prepare(
+        "
+            UPDATE win_line_pick
+            SET is_correct = (is_over = ?)
+            WHERE basho_id = ? AND rikishi_id = ?
+        ",
+    )?;
+    let mut count = 0;
+    for (rikishi_id, went_over) in results {
+        count += stmt.execute(params![went_over, basho_id, rikishi_id])?;
+    }
+    debug!("resolved {} over/under picks for {}", count, basho_id);
+    Ok(())
+}
+
+/// A player's over/under points across the basho of one year.
+pub struct SeasonStanding {
+    pub ord: usize,
+    pub player: Player,
+    pub points: u32,
+    pub resolved: u32,
+    pub basho_count: u32,
+}
+
+impl Rankable for SeasonStanding {
+    fn get_score(&self) -> i32 {
+        self.points as i32
+    }
+
+    fn set_rank(&mut self, ord: usize) {
+        self.ord = ord;
+    }
+}
+
+impl SeasonStanding {
+    pub fn for_year(db: &Connection, year: u16) -> Result<Vec<Self>> {
+        let mut standings = db
+            .prepare(
+                "
+                SELECT
+                    player.*,
+                    SUM(p.is_correct) AS points,
+                    COUNT(*) AS resolved,
+                    COUNT(DISTINCT p.basho_id) AS basho_count
+                FROM win_line_pick AS p
+                JOIN player_info AS player ON player.id = p.player_id
+                WHERE p.basho_id / 100 = ? AND p.is_correct IS NOT NULL
+                GROUP BY player.id
+                ORDER BY points DESC, resolved ASC, player.id
+            ",
+            )?
+            .query_map(params![year], |row| {
+                Ok(Self {
+                    ord: 0,
+                    player: Player::from_row(row)?,
+                    points: row.get("points")?,
+                    resolved: row.get("resolved")?,
+                    basho_count: row.get("basho_count")?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        assign_ord(&mut standings.iter_mut());
+        Ok(standings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{testing, RankSide};
+    use chrono::TimeDelta;
+
+    fn rank(name: RankName, number: u16) -> Rank {
+        Rank {
+            name,
+            number,
+            side: RankSide::East,
+        }
+    }
+
+    #[test]
+    fn lines_lock_when_the_basho_starts() {
+        let db = testing::test_db();
+        let upcoming = BashoId {
+            year: 2026,
+            month: 11,
+        };
+        let started = upcoming.incr(-1);
+        testing::insert_basho(&db, upcoming, Utc::now() + TimeDelta::days(1));
+        testing::insert_basho(&db, started, Utc::now() - TimeDelta::days(1));
+        for basho_id in [upcoming, started] {
+            testing::insert_banzuke(&db, basho_id, 1, "Hoshoryu", "Y1e");
+        }
+        set_line(&db, upcoming, 1, Some(10)).unwrap();
+        assert!(matches!(
+            set_line(&db, started, 1, Some(10)),
+            Err(DataError::BashoHasStarted)
+        ));
+    }
+
+    #[test]
+    fn default_line_follows_rank_and_form() {
+        assert_eq!(default_line(rank(RankName::Yokozuna, 1), None), 11);
+        assert_eq!(default_line(rank(RankName::Sekiwake, 1), Some(8)), 8);
+        assert_eq!(default_line(rank(RankName::Ozeki, 1), Some(12)), 10);
+        assert_eq!(default_line(rank(RankName::Komusubi, 1), Some(4)), 6);
+        assert_eq!(default_line(rank(RankName::Yokozuna, 1), Some(15)), 12);
+    }
+}
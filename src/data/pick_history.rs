@@ -0,0 +1,204 @@
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use itertools::Itertools;
+use rusqlite::{Connection, Result as SqlResult, Transaction};
+use std::collections::HashMap;
+
+use super::{BashoId, PlayerId, Rank, Result, RikishiId};
+
+pub struct PickedRikishi {
+    pub id: RikishiId,
+    pub name: String,
+    pub rank: Rank,
+    pub is_auto: bool,
+    pub is_captain: bool,
+}
+
+/// One save of a player's picks for a basho.
+pub struct PickVersion {
+    pub version: u32,
+    /// Unknown for picks saved before history was kept.
+    pub save_date: Option<DateTime<Utc>>,
+    /// Whether this version was made by filling empty slots with auto-picks.
+    pub is_auto: bool,
+    pub rikishi: Vec<PickedRikishi>,
+}
+
+impl PickVersion {
+    /// Snapshots the player's current rows in `pick` as their next version.
+    pub fn record(
+        txn: &Transaction,
+        player_id: PlayerId,
+        basho_id: BashoId,
+        is_auto: bool,
+    ) -> Result<()> {
+        txn.execute(
+            "
+                INSERT INTO pick_version (player_id, basho_id, version, save_date, is_auto)
+                SELECT :player_id, :basho_id, COALESCE(MAX(version), 0) + 1, :now, :is_auto
+                FROM pick_version
+                WHERE player_id = :player_id AND basho_id = :basho_id
+            ",
+            named_params! {
+                ":player_id": player_id,
+                ":basho_id": basho_id,
+                ":now": Utc::now(),
+                ":is_auto": is_auto,
+            },
+        )?;
+        txn.execute(
+            "
+                INSERT INTO pick_version_rikishi (pick_version_id, rikishi_id, is_auto, is_captain)
+                SELECT ?, rikishi_id, is_auto, is_captain
+                FROM pick
+                WHERE player_id = ? AND basho_id = ?
+            ",
+            params![txn.last_insert_rowid(), player_id, basho_id],
+        )?;
+        Ok(())
+    }
+
+    /// Newest first.
+    pub fn for_player(
+        db: &Connection,
+        player_id: PlayerId,
+        basho_id: BashoId,
+    ) -> Result<Vec<Self>> {
+        let rows = db
+            .prepare(
+                "
+                SELECT
+                    v.version,
+                    v.save_date,
+                    v.is_auto,
+                    b.rikishi_id,
+                    b.family_name,
+                    b.rank,
+                    vr.is_auto,
+                    vr.is_captain
+                FROM pick_version AS v
+                LEFT JOIN pick_version_rikishi AS vr ON vr.pick_version_id = v.id
+                LEFT JOIN banzuke AS b ON b.rikishi_id = vr.rikishi_id AND b.basho_id = v.basho_id
+                WHERE v.player_id = ? AND v.basho_id = ?
+                ORDER BY v.version DESC
+            ",
+            )?
+            .query_map(params![player_id, basho_id], |row| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    row.get::<_, Option<DateTime<Utc>>>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, Option<RikishiId>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<Rank>>(5)?,
+                    row.get::<_, Option<bool>>(6)?,
+                    row.get::<_, Option<bool>>(7)?,
+                ))
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(rows
+            .into_iter()
+            .chunk_by(|row| (row.0, row.1, row.2))
+            .into_iter()
+            .map(|((version, save_date, is_auto), rows)| PickVersion {
+                version,
+                save_date,
+                is_auto,
+                rikishi: rows
+                    .filter_map(|(_, _, _, id, name, rank, is_auto, is_captain)| {
+                        Some(PickedRikishi {
+                            id: id?,
+                            name: name?,
+                            rank: rank?,
+                            is_auto: is_auto?,
+                            is_captain: is_captain?,
+                        })
+                    })
+                    .sorted_by_key(|r| r.rank)
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+/// How many players had each of the most popular rikishi picked at the end of each day of the entry period.
+pub struct PopularityTrend {
+    pub dates: Vec<NaiveDate>,
+    /// Most popular at the deadline first.
+    pub rikishi: Vec<(String, Vec<u32>)>,
+}
+
+impl PopularityTrend {
+    pub fn for_basho(
+        db: &Connection,
+        basho_id: BashoId,
+        deadline: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Self> {
+        // (player, save date, rikishi) for every dated version, oldest first.
+        let versions: Vec<(PlayerId, DateTime<Utc>, Vec<RikishiId>)> = db
+            .prepare(
+                "
+                SELECT v.player_id, v.save_date, GROUP_CONCAT(vr.rikishi_id)
+                FROM pick_version AS v
+                LEFT JOIN pick_version_rikishi AS vr ON vr.pick_version_id = v.id
+                WHERE v.basho_id = ? AND v.save_date IS NOT NULL AND v.save_date <= ?
+                GROUP BY v.id
+                ORDER BY v.save_date
+            ",
+            )?
+            .query_map(params![basho_id, deadline], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get::<_, Option<String>>(2)?
+                        .iter()
+                        .flat_map(|ids| ids.split(','))
+                        .map(|id| id.parse().unwrap())
+                        .collect(),
+                ))
+            })?
+            .collect::<SqlResult<_>>()?;
+        let names: HashMap<RikishiId, String> = db
+            .prepare("SELECT rikishi_id, family_name FROM banzuke WHERE basho_id = ?")?
+            .query_map(params![basho_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqlResult<_>>()?;
+
+        let Some(first) = versions.first().map(|v| v.1.date_naive()) else {
+            return Ok(Self {
+                dates: vec![],
+                rikishi: vec![],
+            });
+        };
+        let dates: Vec<NaiveDate> = first
+            .iter_days()
+            .take_while(|date| *date <= deadline.date_naive())
+            .collect();
+        let mut counts: HashMap<RikishiId, Vec<u32>> = HashMap::new();
+        let mut latest: HashMap<PlayerId, &[RikishiId]> = HashMap::new();
+        let mut remaining = versions.iter().peekable();
+        for (i, date) in dates.iter().enumerate() {
+            let cutoff =
+                (date.and_hms_opt(0, 0, 0).unwrap().and_utc() + TimeDelta::days(1)).min(deadline);
+            while let Some((player_id, _, rikishi)) =
+                remaining.next_if(|(_, save_date, _)| *save_date <= cutoff)
+            {
+                latest.insert(*player_id, rikishi.as_slice());
+            }
+            for rikishi_id in latest.values().flat_map(|ids| ids.iter()) {
+                counts
+                    .entry(*rikishi_id)
+                    .or_insert_with(|| vec![0; dates.len()])[i] += 1;
+            }
+        }
+
+        Ok(Self {
+            rikishi: counts
+                .into_iter()
+                .sorted_by_key(|(id, days)| (std::cmp::Reverse(*days.last().unwrap()), *id))
+                .take(limit)
+                .map(|(id, days)| (names.get(&id).cloned().unwrap_or_default(), days))
+                .collect(),
+            dates,
+        })
+    }
+}
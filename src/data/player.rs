@@ -4,12 +4,15 @@ use rusqlite::{
     Transaction,
 };
 
-use super::{Award, BashoId, Heya, Rank, Result};
+use super::promotion::RankReason;
+use super::{Award, BashoId, Heya, Rank, Result, RikishiId};
 use crate::external::{discord, AuthProvider, ImageSize, UserInfo};
 use askama::Template;
+use itertools::Itertools;
 use rand::random;
 use regex::{regex, Regex};
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::ops::RangeInclusive;
 use url::Url;
 
@@ -271,7 +274,11 @@ pub fn player_id_with_external_user(
 pub struct BashoScore {
     pub basho_id: BashoId,
     pub rank: Option<Rank>,
+    pub rank_reason: Option<RankReason>,
+    pub is_kadoban: bool,
     pub rikishi: [Option<PlayerBashoRikishi>; 5],
+    /// Whether `rikishi` is left empty because the basho's picks are sealed from this viewer.
+    pub picks_sealed: bool,
     pub wins: Option<u8>,
     pub place: Option<u16>,
     pub awards: Vec<Award>,
@@ -282,11 +289,23 @@ impl BashoScore {
         db: &Connection,
         player_id: PlayerId,
         player_name: &str,
+        viewer_id: Option<PlayerId>,
     ) -> Result<Vec<Self>> {
+        let sealed_basho: HashSet<BashoId> = if viewer_id == Some(player_id) {
+            HashSet::new()
+        } else {
+            db.prepare("SELECT id, start_date FROM basho WHERE sealed_picks")?
+                .query_map([], |row| {
+                    Ok((row.get::<_, BashoId>(0)?, row.get::<_, DateTime<Utc>>(1)?))
+                })?
+                .filter_map_ok(|(id, start_date)| (start_date > Utc::now()).then_some(id))
+                .collect::<SqlResult<_>>()?
+        };
+
         // Build mapping of basho_id => PlayerBashoRikishi that can be inserted into the BashoScores later
         let mut basho_rikishi = HashMap::new();
         {
-            struct RikishiRow(BashoId, String, Rank, u8, u8);
+            struct RikishiRow(BashoId, RikishiId, String, Rank, u8, u8);
             let mut stmt = db
                 .prepare(
                     "
@@ -310,6 +329,7 @@ impl BashoScore {
             let rikishi_rows = stmt.query_map(params![player_id], |row| {
                 Ok(RikishiRow(
                     row.get("basho_id")?,
+                    row.get("rikishi_id")?,
                     row.get("family_name")?,
                     row.get("rank")?,
                     row.get("wins")?,
@@ -318,13 +338,17 @@ impl BashoScore {
             })?;
             for rr in rikishi_rows {
                 let rr = rr?;
+                if sealed_basho.contains(&rr.0) {
+                    continue;
+                }
                 let picks = basho_rikishi
                     .entry(rr.0)
                     .or_insert_with(|| [None, None, None, None, None]);
-                picks[rr.2.group().as_index()] = Some(PlayerBashoRikishi {
-                    name: rr.1,
-                    wins: rr.3,
-                    losses: rr.4,
+                picks[rr.3.group().as_index()] = Some(PlayerBashoRikishi {
+                    id: rr.1,
+                    name: rr.2,
+                    wins: rr.4,
+                    losses: rr.5,
                 });
             }
         }
@@ -334,6 +358,8 @@ impl BashoScore {
                 SELECT
                     b.id AS basho_id,
                     pr.rank,
+                    pr.reason AS rank_reason,
+                    COALESCE(pr.is_kadoban, 0) AS is_kadoban,
                     COALESCE(r.wins, e.wins) AS wins,
                     COALESCE(r.rank, e.rank) AS place,
                     (
@@ -356,7 +382,10 @@ impl BashoScore {
                 Ok(BashoScore {
                     basho_id,
                     rank: row.get("rank")?,
+                    rank_reason: row.get("rank_reason")?,
+                    is_kadoban: row.get("is_kadoban")?,
                     rikishi: basho_rikishi.remove(&basho_id).unwrap_or_default(),
+                    picks_sealed: sealed_basho.contains(&basho_id),
                     wins: row.get("wins")?,
                     place: row.get("place")?,
                     awards: Award::parse_list(row.get("awards")?),
@@ -368,9 +397,124 @@ impl BashoScore {
     }
 }
 
+/// One basho that two players both entered, for the head-to-head page.
+#[derive(Debug)]
+pub struct HeadToHead {
+    pub basho_id: BashoId,
+    pub a: BashoScore,
+    pub b: BashoScore,
+    pub shared_picks: Vec<String>,
+    /// Running record from player a's point of view, including this basho.
+    pub record: HeadToHeadRecord,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HeadToHeadRecord {
+    pub wins: u16,
+    pub losses: u16,
+    pub draws: u16,
+}
+
+impl HeadToHead {
+    /// Pairs up the bashos both players have a score for. Takes the output of `BashoScore::with_player_id` for each player and returns newest first.
+    pub fn between(a_scores: Vec<BashoScore>, b_scores: Vec<BashoScore>) -> Vec<Self> {
+        let mut b_by_basho: HashMap<BashoId, BashoScore> = b_scores
+            .into_iter()
+            .filter(|s| s.wins.is_some())
+            .map(|s| (s.basho_id, s))
+            .collect();
+        let mut record = HeadToHeadRecord::default();
+        let mut bashos: Vec<Self> = a_scores
+            .into_iter()
+            .rev()
+            .filter(|a| a.wins.is_some())
+            .filter_map(|a| {
+                let b = b_by_basho.remove(&a.basho_id)?;
+                match Self::compare(&a, &b) {
+                    Ordering::Greater => record.wins += 1,
+                    Ordering::Less => record.losses += 1,
+                    Ordering::Equal => record.draws += 1,
+                }
+                let shared_picks = a
+                    .rikishi
+                    .iter()
+                    .flatten()
+                    .filter(|ra| b.rikishi.iter().flatten().any(|rb| rb.name == ra.name))
+                    .map(|r| r.name.clone())
+                    .collect();
+                Some(Self {
+                    basho_id: a.basho_id,
+                    a,
+                    b,
+                    shared_picks,
+                    record,
+                })
+            })
+            .collect();
+        bashos.reverse();
+        bashos
+    }
+
+    /// Orders two players' results in the same basho like the leaderboard does: by wins, then by place, which breaks ties on the yusho pick.
+    fn compare(a: &BashoScore, b: &BashoScore) -> Ordering {
+        a.wins.cmp(&b.wins).then_with(|| match (a.place, b.place) {
+            (Some(a_place), Some(b_place)) => b_place.cmp(&a_place),
+            _ => Ordering::Equal,
+        })
+    }
+
+    /// `Some(true)` if player a won this basho, `Some(false)` if player b did, `None` for a draw.
+    pub fn a_won(&self) -> Option<bool> {
+        match Self::compare(&self.a, &self.b) {
+            Ordering::Greater => Some(true),
+            Ordering::Less => Some(false),
+            Ordering::Equal => None,
+        }
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct PlayerBashoRikishi {
+    pub id: RikishiId,
     pub name: String,
     pub wins: u8,
     pub losses: u8,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(basho_id: i64, wins: u8, place: u16) -> BashoScore {
+        BashoScore {
+            basho_id: BashoId::from(basho_id),
+            rank: None,
+            rank_reason: None,
+            is_kadoban: false,
+            rikishi: Default::default(),
+            picks_sealed: false,
+            wins: Some(wins),
+            place: Some(place),
+            awards: vec![],
+        }
+    }
+
+    #[test]
+    fn equal_wins_are_decided_by_place() {
+        let a = vec![
+            score(202611, 40, 2),
+            score(202609, 38, 1),
+            score(202607, 35, 4),
+        ];
+        let b = vec![
+            score(202611, 40, 1),
+            score(202609, 36, 3),
+            score(202607, 35, 4),
+        ];
+        let h2h = HeadToHead::between(a, b);
+        let outcomes: Vec<_> = h2h.iter().map(HeadToHead::a_won).collect();
+        assert_eq!(outcomes, [Some(false), Some(true), None]);
+        let record = h2h[0].record;
+        assert_eq!((record.wins, record.losses, record.draws), (1, 1, 1));
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use rusqlite::{Connection, Result as SqlResult};
+
+use super::player::BashoScore;
+use super::{BashoId, PlayerId, Rank, RankGroup, Result, RikishiId};
+
+const PICK_LIST_LEN: usize = 3;
+
+/// Aggregate statistics for the player page, derived mostly from the player's `BashoScore` history.
+#[derive(Debug)]
+pub struct PlayerStats {
+    pub basho_count: usize,
+    /// Average wins of the pick in each rank group slot, in `RankGroup` index order.
+    pub group_averages: Vec<(RankGroup, Option<f64>)>,
+    pub best_picks: Vec<PickStat>,
+    pub worst_picks: Vec<PickStat>,
+    pub favorite_rikishi: Vec<FavoriteRikishi>,
+    /// Bashos where the player scored as well as the Max baseline on the leaderboard.
+    pub matched_max: usize,
+    /// Bashos where the player scored better than the Min baseline on the leaderboard.
+    pub beat_min: usize,
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    /// Newest first.
+    pub percentiles: Vec<(BashoId, f64)>,
+}
+
+/// A rikishi's record summed over every basho the player picked them.
+#[derive(Debug)]
+pub struct PickStat {
+    pub name: String,
+    pub times: usize,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+#[derive(Debug)]
+pub struct FavoriteRikishi {
+    pub name: String,
+    pub times: usize,
+    pub wins: u32,
+}
+
+impl PlayerStats {
+    /// `scores` is the output of `BashoScore::with_player_id`, newest first.
+    pub fn compute(db: &Connection, player_id: PlayerId, scores: &[BashoScore]) -> Result<Self> {
+        let entered = || scores.iter().filter(|s| s.wins.is_some());
+
+        let group_averages = (0..RankGroup::count())
+            .map(|i| {
+                let wins = entered()
+                    .filter_map(|s| s.rikishi[i].as_ref().map(|r| r.wins as f64))
+                    .collect::<Vec<_>>();
+                let avg = if wins.is_empty() {
+                    None
+                } else {
+                    Some(wins.iter().sum::<f64>() / wins.len() as f64)
+                };
+                (RankGroup::from_index(i), avg)
+            })
+            .collect();
+
+        let picks = pick_totals(entered());
+        let favorite_rikishi = picks
+            .iter()
+            .sorted_by_key(|p| (usize::MAX - p.times, u32::MAX - p.wins))
+            .take(PICK_LIST_LEN)
+            .map(|p| FavoriteRikishi {
+                name: p.name.clone(),
+                times: p.times,
+                wins: p.wins,
+            })
+            .collect();
+        let worst_picks = picks
+            .iter()
+            .rev()
+            .take(PICK_LIST_LEN)
+            .map(|p| PickStat {
+                name: p.name.clone(),
+                times: p.times,
+                wins: p.wins,
+                losses: p.losses,
+            })
+            .collect();
+        let best_picks = picks.into_iter().take(PICK_LIST_LEN).collect();
+
+        let baselines = Self::fetch_baselines(db, player_id)?;
+        let (matched_max, beat_min) = entered().fold((0, 0), |(max, min), s| {
+            match (s.wins, baselines.get(&s.basho_id)) {
+                (Some(wins), Some(&(min_total, max_total))) => (
+                    max + usize::from(wins >= max_total),
+                    min + usize::from(wins > min_total),
+                ),
+                _ => (max, min),
+            }
+        });
+
+        // The newest basho may not have started yet, which shouldn't break the current streak.
+        let runs = scores
+            .iter()
+            .skip_while(|s| s.wins.is_none() && s.basho_id == scores[0].basho_id)
+            .chunk_by(|s| s.wins.is_some())
+            .into_iter()
+            .map(|(entered, run)| (entered, run.count()))
+            .collect::<Vec<_>>();
+        let current_streak = match runs.first() {
+            Some(&(true, len)) => len,
+            _ => 0,
+        };
+        let longest_streak = runs
+            .iter()
+            .filter(|(entered, _)| *entered)
+            .map(|(_, len)| *len)
+            .max()
+            .unwrap_or(0);
+
+        let entrants = Self::fetch_entrants(db)?;
+        let percentiles = entered()
+            .filter_map(|s| {
+                let place = s.place? as f64;
+                let entrants = *entrants.get(&s.basho_id)? as f64;
+                Some((s.basho_id, 100.0 * (1.0 - (place - 1.0) / entrants)))
+            })
+            .collect();
+
+        Ok(Self {
+            basho_count: entered().count(),
+            group_averages,
+            best_picks,
+            worst_picks,
+            favorite_rikishi,
+            matched_max,
+            beat_min,
+            current_streak,
+            longest_streak,
+            percentiles,
+        })
+    }
+
+    /// Min and Max baseline totals for each basho the player entered, using the same rules as the leaderboard's `make_min_max_results`: the worst and best non-kyujyo rikishi in each rank group.
+    fn fetch_baselines(db: &Connection, player_id: PlayerId) -> Result<HashMap<BashoId, (u8, u8)>> {
+        let rows = db
+            .prepare(
+                "
+                    SELECT
+                        b.basho_id,
+                        b.rank,
+                        COALESCE(SUM(t.win = 1), 0) AS wins
+                    FROM banzuke AS b
+                    LEFT NATURAL JOIN torikumi AS t
+                    WHERE
+                        b.basho_id IN (SELECT basho_id FROM basho_result WHERE player_id = ?)
+                        AND NOT b.kyujyo
+                    GROUP BY b.basho_id, b.rikishi_id
+                ",
+            )?
+            .query_map(params![player_id], |row| {
+                Ok((
+                    row.get::<_, BashoId>(0)?,
+                    row.get::<_, Rank>(1)?,
+                    row.get::<_, u8>(2)?,
+                ))
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        let mut groups: HashMap<BashoId, [Option<(u8, u8)>; 5]> = HashMap::new();
+        for (basho_id, rank, wins) in rows {
+            let index = rank.group().as_index();
+            if index >= RankGroup::count() {
+                continue;
+            }
+            let slot = &mut groups.entry(basho_id).or_default()[index];
+            *slot = Some(slot.map_or((wins, wins), |(min, max)| (min.min(wins), max.max(wins))));
+        }
+        Ok(groups
+            .into_iter()
+            .map(|(basho_id, slots)| {
+                let slots = slots.iter().flatten();
+                (
+                    basho_id,
+                    (
+                        slots.clone().map(|(min, _)| min).sum(),
+                        slots.map(|(_, max)| max).sum(),
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    fn fetch_entrants(db: &Connection) -> Result<HashMap<BashoId, u32>> {
+        Ok(db
+            .prepare(
+                "
+                    SELECT
+                        basho.id,
+                        (SELECT COUNT(*) FROM basho_result WHERE basho_id = basho.id)
+                        + (SELECT COUNT(*) FROM external_basho_player WHERE basho_id = basho.id)
+                    FROM basho
+                ",
+            )?
+            .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqlResult<_>>()?)
+    }
+}
+
+/// Totals each picked rikishi's wins and losses across `scores`, best first. The name is taken from the newest basho, since `scores` is newest first.
+fn pick_totals<'a>(scores: impl Iterator<Item = &'a BashoScore>) -> Vec<PickStat> {
+    let mut totals: HashMap<RikishiId, PickStat> = HashMap::new();
+    for r in scores.flat_map(|s| s.rikishi.iter().flatten()) {
+        let total = totals.entry(r.id).or_insert_with(|| PickStat {
+            name: r.name.clone(),
+            times: 0,
+            wins: 0,
+            losses: 0,
+        });
+        total.times += 1;
+        total.wins += r.wins as u32;
+        total.losses += r.losses as u32;
+    }
+    totals
+        .into_values()
+        .sorted_by_key(|p| (u32::MAX - p.wins, p.losses, p.name.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::player::PlayerBashoRikishi;
+
+    fn score(month: u8, picks: [Option<(RikishiId, &str, u8, u8)>; 5]) -> BashoScore {
+        BashoScore {
+            basho_id: BashoId { year: 2025, month },
+            rank: None,
+            rank_reason: None,
+            is_kadoban: false,
+            rikishi: picks.map(|pick| {
+                pick.map(|(id, name, wins, losses)| PlayerBashoRikishi {
+                    id,
+                    name: name.to_string(),
+                    wins,
+                    losses,
+                })
+            }),
+            picks_sealed: false,
+            wins: Some(0),
+            place: None,
+            awards: vec![],
+        }
+    }
+
+    #[test]
+    fn pick_totals_sum_each_rikishi_across_basho() {
+        let scores = [
+            score(
+                3,
+                [
+                    Some((1, "Hoshoryu", 10, 5)),
+                    Some((2, "Onosato", 9, 6)),
+                    None,
+                    None,
+                    None,
+                ],
+            ),
+            score(
+                1,
+                [
+                    None,
+                    Some((1, "Hoshoryu", 4, 11)),
+                    Some((3, "Wakatakakage", 12, 3)),
+                    None,
+                    None,
+                ],
+            ),
+        ];
+        let totals = pick_totals(scores.iter());
+        let summary = totals
+            .iter()
+            .map(|p| (p.name.as_str(), p.times, p.wins, p.losses))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("Hoshoryu", 2, 14, 16),
+                ("Wakatakakage", 1, 12, 3),
+                ("Onosato", 1, 9, 6),
+            ]
+        );
+    }
+}
@@ -0,0 +1,578 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{Result as SqlResult, ToSql, Transaction};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::leaders::HistoricLeader;
+use super::{BashoId, PlayerId, Rank, RankName, RankSide, Result};
+
+/// Sekiwake and Komusubi are promoted to Ozeki after finishing in this top fraction of entrants in each of the last `OZEKI_RUN_BASHO` basho.
+const OZEKI_FINISH: f64 = 0.1;
+const OZEKI_RUN_BASHO: usize = 3;
+/// A freshly demoted Ozeki returns immediately with a finish in this top fraction of entrants.
+const OZEKI_RETURN_FINISH: f64 = 0.1;
+/// Promotions only fill open Yokozuna and Ozeki slots. Those already holding the rank keep it even when there are more of them than this.
+const YOKOZUNA_SLOTS: usize = 2;
+const OZEKI_SLOTS: usize = 4;
+
+/// Why a player holds their rank on a banzuke. The numeric values are stored in the `player_rank.reason` column so they must never be reused.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RankReason {
+    /// Seeded from past-year wins because there was no previous banzuke.
+    Initial = 1,
+    Debut = 2,
+    KachiKoshi = 3,
+    MakeKoshi = 4,
+    Kyujo = 5,
+    OzekiPromotion = 6,
+    YokozunaPromotion = 7,
+    Kadoban = 8,
+    KadobanCleared = 9,
+    OzekiDemotion = 10,
+    OzekiReturn = 11,
+}
+
+impl RankReason {
+    fn from_i64(num: i64) -> Option<Self> {
+        match num {
+            1 => Some(Self::Initial),
+            2 => Some(Self::Debut),
+            3 => Some(Self::KachiKoshi),
+            4 => Some(Self::MakeKoshi),
+            5 => Some(Self::Kyujo),
+            6 => Some(Self::OzekiPromotion),
+            7 => Some(Self::YokozunaPromotion),
+            8 => Some(Self::Kadoban),
+            9 => Some(Self::KadobanCleared),
+            10 => Some(Self::OzekiDemotion),
+            11 => Some(Self::OzekiReturn),
+            _ => None,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Initial => "Seeded by wins over the past year",
+            Self::Debut => "Entered the banzuke",
+            Self::KachiKoshi => "Kachi-koshi: beat the score expected at their rank",
+            Self::MakeKoshi => "Make-koshi: fell short of the score expected at their rank",
+            Self::Kyujo => "Kyujo: sat out the basho",
+            Self::OzekiPromotion => "Promoted to Ozeki after three strong basho in sanyaku",
+            Self::YokozunaPromotion => "Promoted to Yokozuna after consecutive yusho-level basho",
+            Self::Kadoban => "Kadoban: make-koshi as Ozeki",
+            Self::KadobanCleared => "Kachi-koshi as a kadoban Ozeki",
+            Self::OzekiDemotion => "Demoted from Ozeki after a second straight make-koshi",
+            Self::OzekiReturn => "Returned to Ozeki with a strong basho right after demotion",
+        }
+    }
+}
+
+impl FromSql for RankReason {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        value
+            .as_i64()
+            .and_then(|num| Self::from_i64(num).ok_or(FromSqlError::OutOfRange(num)))
+    }
+}
+
+impl ToSql for RankReason {
+    fn to_sql(&self) -> SqlResult<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(*self as u8))
+    }
+}
+
+#[derive(Debug)]
+pub struct NextRank {
+    pub player_id: PlayerId,
+    pub rank: Rank,
+    pub reason: RankReason,
+    pub is_kadoban: bool,
+    pub past_year_wins: u32,
+}
+
+struct PrevRank {
+    rank: Rank,
+    /// Position on the previous banzuke, 0 being the top.
+    pos: usize,
+    reason: Option<RankReason>,
+    is_kadoban: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Finish {
+    wins: u8,
+    place: usize,
+    /// Fraction of entrants who scored higher; 0 for the yusho.
+    fraction: f64,
+}
+
+struct Candidate {
+    player_id: PlayerId,
+    past_year_wins: u32,
+    prev: Option<PrevRank>,
+    /// Finishes in the last basho and the ones before it, most recent first.
+    finishes: [Option<Finish>; OZEKI_RUN_BASHO],
+    /// Rank held in each of the basho in `finishes`.
+    run_ranks: [Option<Rank>; OZEKI_RUN_BASHO],
+}
+
+/// Builds the banzuke for the basho after `last_basho` by moving each player up or down from their previous rank according to their result, in the style of the real banzuke committee. Players are on the banzuke if they entered any basho in the banzuke range; when there is no previous banzuke at all, ranks are seeded from past-year wins.
+pub fn next_banzuke(txn: &Transaction, last_basho: BashoId) -> Result<Vec<NextRank>> {
+    let leaders =
+        HistoricLeader::with_basho_range(txn, &last_basho.next().range_for_banzuke(), u32::MAX)?;
+    let mut prev: HashMap<PlayerId, PrevRank> = HashMap::new();
+    {
+        let mut rows = txn
+            .prepare(
+                "
+                SELECT player_id, rank, reason, is_kadoban
+                FROM player_rank
+                WHERE before_basho_id = ?
+            ",
+            )?
+            .query_map(params![last_basho], |row| {
+                Ok((
+                    row.get::<_, PlayerId>(0)?,
+                    PrevRank {
+                        rank: row.get(1)?,
+                        pos: 0, // set below
+                        reason: row.get(2)?,
+                        is_kadoban: row.get(3)?,
+                    },
+                ))
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        rows.sort_by_key(|(_, p)| p.rank);
+        for (pos, (player_id, mut p)) in rows.into_iter().enumerate() {
+            p.pos = pos;
+            prev.insert(player_id, p);
+        }
+    }
+    let mut finishes = Vec::with_capacity(OZEKI_RUN_BASHO);
+    let mut run_ranks = Vec::with_capacity(OZEKI_RUN_BASHO);
+    let mut last_scores = vec![];
+    for i in 0..OZEKI_RUN_BASHO {
+        let basho_id = last_basho.incr(-(i as isize));
+        let (by_player, scores) = basho_finishes(txn, basho_id)?;
+        if i == 0 {
+            last_scores = scores;
+        }
+        finishes.push(by_player);
+        run_ranks.push(
+            txn.prepare("SELECT player_id, rank FROM player_rank WHERE before_basho_id = ?")?
+                .query_map(params![basho_id], |row| {
+                    Ok((row.get::<_, PlayerId>(0)?, row.get::<_, Rank>(1)?))
+                })?
+                .collect::<SqlResult<HashMap<_, _>>>()?,
+        );
+    }
+
+    let prev_count = prev.len();
+    let candidates: Vec<Candidate> = leaders
+        .into_iter()
+        .map(|l| Candidate {
+            player_id: l.player.id,
+            past_year_wins: l.wins.total.unwrap_or(0),
+            prev: prev.remove(&l.player.id),
+            finishes: std::array::from_fn(|i| finishes[i].get(&l.player.id).copied()),
+            run_ranks: std::array::from_fn(|i| run_ranks[i].get(&l.player.id).copied()),
+        })
+        .collect();
+    Ok(assign_ranks(&candidates, prev_count, &last_scores))
+}
+
+/// Returns each entrant's finish in a basho along with all entrants' scores from highest to lowest.
+fn basho_finishes(
+    txn: &Transaction,
+    basho_id: BashoId,
+) -> Result<(HashMap<PlayerId, Finish>, Vec<u8>)> {
+    let entrants = txn
+        .prepare(
+            "
+                SELECT player_id, wins
+                FROM basho_result
+                WHERE basho_id = :basho_id
+
+                UNION ALL
+
+                SELECT p.id, e.wins
+                FROM external_basho_player AS e
+                JOIN player AS p ON p.name = e.name
+                WHERE e.basho_id = :basho_id
+            ",
+        )?
+        .query_map(named_params! {":basho_id": basho_id}, |row| {
+            Ok((row.get::<_, PlayerId>(0)?, row.get::<_, u8>(1)?))
+        })?
+        .collect::<SqlResult<Vec<_>>>()?;
+    let mut scores: Vec<u8> = entrants.iter().map(|(_, wins)| *wins).collect();
+    scores.sort_unstable_by(|a, b| b.cmp(a));
+    let by_player = entrants
+        .into_iter()
+        .map(|(player_id, wins)| {
+            let higher = scores.partition_point(|s| *s > wins);
+            (
+                player_id,
+                Finish {
+                    wins,
+                    place: higher + 1,
+                    fraction: higher as f64 / scores.len() as f64,
+                },
+            )
+        })
+        .collect();
+    Ok((by_player, scores))
+}
+
+/// The score a player needed for kachi-koshi: whatever the entrant finishing at the same relative position as their rank scored.
+fn expected_score(pos: usize, prev_count: usize, last_scores: &[u8]) -> u8 {
+    if last_scores.is_empty() {
+        return 0;
+    }
+    let i = pos * last_scores.len() / prev_count.max(1);
+    last_scores[i.min(last_scores.len() - 1)]
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Tier {
+    Yokozuna,
+    Ozeki,
+    Rest,
+}
+
+struct Placement<'a> {
+    candidate: &'a Candidate,
+    tier: Tier,
+    /// Position on the new banzuke the player is aiming for; only meaningful within `Tier::Rest`.
+    target: f64,
+    reason: RankReason,
+    is_kadoban: bool,
+}
+
+impl Placement<'_> {
+    fn is_promotion(&self) -> bool {
+        matches!(
+            self.reason,
+            RankReason::YokozunaPromotion | RankReason::OzekiPromotion | RankReason::OzekiReturn
+        )
+    }
+}
+
+fn assign_ranks(candidates: &[Candidate], prev_count: usize, last_scores: &[u8]) -> Vec<NextRank> {
+    let mut placements: Vec<Placement> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, c)| placement(c, i, prev_count, last_scores, Tier::Yokozuna))
+        .collect();
+
+    // Promotions beyond the open slots go to the better basho; the rest are placed as if they hadn't qualified.
+    for (tier, slots, fallback) in [
+        (Tier::Yokozuna, YOKOZUNA_SLOTS, Tier::Ozeki),
+        (Tier::Ozeki, OZEKI_SLOTS, Tier::Rest),
+    ] {
+        let sitting = placements
+            .iter()
+            .filter(|p| p.tier == tier && !p.is_promotion())
+            .count();
+        let mut promoted = (0..placements.len())
+            .filter(|&i| placements[i].tier == tier && placements[i].is_promotion())
+            .collect::<Vec<_>>();
+        promoted.sort_by(|&a, &b| by_last_basho(placements[a].candidate, placements[b].candidate));
+        for i in promoted.into_iter().skip(slots.saturating_sub(sitting)) {
+            placements[i] = placement(&candidates[i], i, prev_count, last_scores, fallback);
+        }
+    }
+
+    // Within Yokozuna and Ozeki the better basho earns the higher slot; the rest are ordered by target.
+    placements.sort_by(|a, b| {
+        a.tier.cmp(&b.tier).then_with(|| match a.tier {
+            Tier::Yokozuna | Tier::Ozeki => by_last_basho(a.candidate, b.candidate),
+            Tier::Rest => a
+                .target
+                .total_cmp(&b.target)
+                .then_with(|| last_wins(b.candidate).cmp(&last_wins(a.candidate))),
+        })
+    });
+
+    let mut tier_index = 0;
+    let mut last_tier = Tier::Yokozuna;
+    let mut rest_rank = Rank {
+        name: RankName::Sekiwake,
+        number: 1,
+        side: RankSide::East,
+    };
+    placements
+        .into_iter()
+        .map(|p| {
+            if p.tier != last_tier {
+                last_tier = p.tier;
+                tier_index = 0;
+            }
+            let rank = match p.tier {
+                Tier::Yokozuna => named_rank(RankName::Yokozuna, tier_index),
+                Tier::Ozeki => named_rank(RankName::Ozeki, tier_index),
+                Tier::Rest => {
+                    let rank = rest_rank;
+                    rest_rank = rest_rank.next_lower();
+                    rank
+                }
+            };
+            tier_index += 1;
+            NextRank {
+                player_id: p.candidate.player_id,
+                rank,
+                reason: p.reason,
+                is_kadoban: p.is_kadoban,
+                past_year_wins: p.candidate.past_year_wins,
+            }
+        })
+        .collect()
+}
+
+/// Where a candidate lands on the next banzuke. `highest` is the highest tier they may be promoted into, so promotions that don't fit can be placed again without them.
+fn placement<'a>(
+    c: &'a Candidate,
+    i: usize,
+    prev_count: usize,
+    last_scores: &[u8],
+    highest: Tier,
+) -> Placement<'a> {
+    let n = prev_count as f64;
+    let place = |tier, target, reason, is_kadoban| Placement {
+        candidate: c,
+        tier,
+        target,
+        reason,
+        is_kadoban,
+    };
+    let last = c.finishes[0];
+    let Some(prev) = &c.prev else {
+        return if prev_count == 0 {
+            place(Tier::Rest, i as f64, RankReason::Initial, false)
+        } else {
+            // Debuts go below everyone returning, ordered by their result.
+            let finish_pos = last.map_or(n, |f| f.fraction * n);
+            place(Tier::Rest, n + finish_pos, RankReason::Debut, false)
+        };
+    };
+    let is_kachi_koshi =
+        last.is_some_and(|f| f.wins >= expected_score(prev.pos, prev_count, last_scores));
+    let result_reason = match last {
+        None => RankReason::Kyujo,
+        Some(_) if is_kachi_koshi => RankReason::KachiKoshi,
+        Some(_) => RankReason::MakeKoshi,
+    };
+    match prev.rank.name {
+        // Yokozuna are never demoted; they fall off the banzuke only by sitting out the whole banzuke range.
+        RankName::Yokozuna => place(Tier::Yokozuna, 0.0, result_reason, false),
+        RankName::Ozeki if highest <= Tier::Yokozuna && is_yokozuna_run(c) => {
+            place(Tier::Yokozuna, 0.0, RankReason::YokozunaPromotion, false)
+        }
+        RankName::Ozeki if is_kachi_koshi => place(
+            Tier::Ozeki,
+            0.0,
+            if prev.is_kadoban {
+                RankReason::KadobanCleared
+            } else {
+                RankReason::KachiKoshi
+            },
+            false,
+        ),
+        RankName::Ozeki if !prev.is_kadoban => place(Tier::Ozeki, 0.0, RankReason::Kadoban, true),
+        // Demoted Ozeki go straight to the top of Sekiwake.
+        RankName::Ozeki => place(Tier::Rest, -1.0, RankReason::OzekiDemotion, false),
+        RankName::Sekiwake | RankName::Komusubi if highest <= Tier::Ozeki && is_ozeki_run(c) => {
+            place(Tier::Ozeki, 0.0, RankReason::OzekiPromotion, false)
+        }
+        _ if highest <= Tier::Ozeki
+            && prev.reason == Some(RankReason::OzekiDemotion)
+            && last.is_some_and(|f| f.fraction <= OZEKI_RETURN_FINISH) =>
+        {
+            place(Tier::Ozeki, 0.0, RankReason::OzekiReturn, false)
+        }
+        _ => {
+            // Move halfway from the old position toward the finishing position, but kachi-koshi never drops a player and make-koshi never lifts one.
+            let pos = prev.pos as f64;
+            let finish_pos = last.map_or(n, |f| f.fraction * n);
+            let target = (pos + finish_pos) / 2.0;
+            let target = if is_kachi_koshi {
+                target.min(pos - 0.5)
+            } else {
+                target.max(pos + 0.5)
+            };
+            place(Tier::Rest, target, result_reason, false)
+        }
+    }
+}
+
+/// Yokozuna and Ozeki aren't limited to one east-west pair, so the i-th of them is numbered directly.
+fn named_rank(name: RankName, i: usize) -> Rank {
+    Rank {
+        name,
+        number: (i / 2 + 1) as u16,
+        side: if i % 2 == 0 {
+            RankSide::East
+        } else {
+            RankSide::West
+        },
+    }
+}
+
+/// A top finish in each of the last `OZEKI_RUN_BASHO` basho, all of them at Sekiwake or Komusubi.
+fn is_ozeki_run(c: &Candidate) -> bool {
+    c.finishes.iter().zip(&c.run_ranks).all(|(f, rank)| {
+        f.is_some_and(|f| f.fraction <= OZEKI_FINISH)
+            && rank.is_some_and(|r| matches!(r.name, RankName::Sekiwake | RankName::Komusubi))
+    })
+}
+
+/// A yusho as Ozeki following a yusho or jun-yusho.
+fn is_yokozuna_run(c: &Candidate) -> bool {
+    c.finishes[0].is_some_and(|f| f.place == 1) && c.finishes[1].is_some_and(|f| f.place <= 2)
+}
+
+/// Better last basho first, then higher previous rank.
+fn by_last_basho(a: &Candidate, b: &Candidate) -> Ordering {
+    last_wins(b)
+        .cmp(&last_wins(a))
+        .then_with(|| prev_pos(a).cmp(&prev_pos(b)))
+}
+
+fn last_wins(c: &Candidate) -> u8 {
+    c.finishes[0].map_or(0, |f| f.wins)
+}
+
+fn prev_pos(c: &Candidate) -> usize {
+    c.prev.as_ref().map_or(usize::MAX, |p| p.pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn finish(wins: u8, place: usize, fraction: f64) -> Option<Finish> {
+        Some(Finish {
+            wins,
+            place,
+            fraction,
+        })
+    }
+
+    fn candidate(
+        player_id: PlayerId,
+        prev: Option<(&str, usize, bool)>,
+        finishes: [Option<Finish>; OZEKI_RUN_BASHO],
+    ) -> Candidate {
+        let prev = prev.map(|(rank, pos, is_kadoban)| PrevRank {
+            rank: Rank::from_str(rank).unwrap(),
+            pos,
+            reason: None,
+            is_kadoban,
+        });
+        Candidate {
+            player_id,
+            past_year_wins: 0,
+            run_ranks: [prev.as_ref().map(|p| p.rank); OZEKI_RUN_BASHO],
+            prev,
+            finishes,
+        }
+    }
+
+    fn rank_of(ranks: &[NextRank], player_id: PlayerId) -> &NextRank {
+        ranks.iter().find(|r| r.player_id == player_id).unwrap()
+    }
+
+    #[test]
+    fn initial_banzuke_follows_candidate_order() {
+        let candidates = [
+            candidate(1, None, [finish(50, 1, 0.0), None, None]),
+            candidate(2, None, [finish(40, 2, 0.5), None, None]),
+        ];
+        let ranks = assign_ranks(&candidates, 0, &[50, 40]);
+        assert_eq!(Rank::from_str("S1e").unwrap(), rank_of(&ranks, 1).rank);
+        assert_eq!(Rank::from_str("S1w").unwrap(), rank_of(&ranks, 2).rank);
+        assert_eq!(RankReason::Initial, rank_of(&ranks, 1).reason);
+    }
+
+    #[test]
+    fn kachi_koshi_promotes_and_make_koshi_demotes() {
+        let candidates = [
+            candidate(
+                1,
+                Some(("S1e", 0, false)),
+                [finish(30, 3, 2.0 / 3.0), None, None],
+            ),
+            candidate(
+                2,
+                Some(("S1w", 1, false)),
+                [finish(40, 2, 1.0 / 3.0), None, None],
+            ),
+            candidate(3, Some(("K1e", 2, false)), [finish(50, 1, 0.0), None, None]),
+        ];
+        let ranks = assign_ranks(&candidates, 3, &[50, 40, 30]);
+        assert_eq!(Rank::from_str("S1e").unwrap(), rank_of(&ranks, 2).rank);
+        assert_eq!(Rank::from_str("S1w").unwrap(), rank_of(&ranks, 3).rank);
+        assert_eq!(RankReason::KachiKoshi, rank_of(&ranks, 3).reason);
+        assert_eq!(Rank::from_str("K1e").unwrap(), rank_of(&ranks, 1).rank);
+        assert_eq!(RankReason::MakeKoshi, rank_of(&ranks, 1).reason);
+    }
+
+    #[test]
+    fn ozeki_goes_kadoban_then_demoted() {
+        let scores = [50, 10];
+        let make_koshi = [finish(10, 2, 0.5), None, None];
+        let ranks = assign_ranks(
+            &[
+                candidate(1, Some(("O1e", 0, false)), make_koshi),
+                candidate(2, Some(("S1e", 1, false)), [finish(50, 1, 0.0), None, None]),
+            ],
+            2,
+            &scores,
+        );
+        let ozeki = rank_of(&ranks, 1);
+        assert_eq!(RankName::Ozeki, ozeki.rank.name);
+        assert_eq!(RankReason::Kadoban, ozeki.reason);
+        assert!(ozeki.is_kadoban);
+
+        let ranks = assign_ranks(
+            &[candidate(1, Some(("O1e", 0, true)), make_koshi)],
+            2,
+            &scores,
+        );
+        let demoted = rank_of(&ranks, 1);
+        assert_eq!(Rank::from_str("S1e").unwrap(), demoted.rank);
+        assert_eq!(RankReason::OzekiDemotion, demoted.reason);
+    }
+
+    #[test]
+    fn sanyaku_run_earns_ozeki() {
+        let run = [finish(50, 1, 0.0), finish(48, 2, 0.05), finish(47, 1, 0.0)];
+        let ranks = assign_ranks(&[candidate(1, Some(("K1w", 3, false)), run)], 4, &[50]);
+        assert_eq!(Rank::from_str("O1e").unwrap(), ranks[0].rank);
+        assert_eq!(RankReason::OzekiPromotion, ranks[0].reason);
+    }
+
+    #[test]
+    fn maegashira_run_does_not_earn_ozeki() {
+        let run = [finish(50, 1, 0.0), finish(48, 2, 0.05), finish(47, 1, 0.0)];
+        let mut c = candidate(1, Some(("K1w", 3, false)), run);
+        c.run_ranks[2] = Some(Rank::from_str("M2e").unwrap());
+        let ranks = assign_ranks(&[c], 4, &[50]);
+        assert_eq!(Rank::from_str("S1e").unwrap(), ranks[0].rank);
+        assert_eq!(RankReason::KachiKoshi, ranks[0].reason);
+    }
+
+    #[test]
+    fn ozeki_promotion_needs_an_open_slot() {
+        let kachi_koshi = [finish(40, 2, 0.1), None, None];
+        let run = [finish(50, 1, 0.0), finish(48, 2, 0.05), finish(47, 1, 0.0)];
+        let mut candidates = (0..OZEKI_SLOTS)
+            .map(|i| candidate(i as PlayerId + 1, Some(("O1e", i, false)), kachi_koshi))
+            .collect::<Vec<_>>();
+        candidates.push(candidate(99, Some(("S1e", OZEKI_SLOTS, false)), run));
+        let ranks = assign_ranks(&candidates, OZEKI_SLOTS + 1, &[50, 40]);
+        assert_eq!(Rank::from_str("S1e").unwrap(), rank_of(&ranks, 99).rank);
+        assert_eq!(RankReason::KachiKoshi, rank_of(&ranks, 99).reason);
+    }
+}
@@ -1,6 +1,11 @@
 use std::collections::{HashMap, HashSet};
 
-use super::{Award, BashoId, BashoInfo, DataError, Day, DbConn, PlayerId, Rank, Result, RikishiId};
+use super::heya::{HeyaId, HeyaStanding};
+use super::heya_post::{HeyaPost, HeyaPostId};
+use super::simulation::{SimulatedOdds, TOP_PLACES};
+use super::{
+    Award, BashoId, BashoInfo, DataError, Day, DbConn, Heya, PlayerId, Rank, Result, RikishiId,
+};
 use chrono::{Duration, Utc};
 use itertools::Itertools;
 use rusqlite::{types::FromSqlResult, Connection, Row, RowIndex};
@@ -19,6 +24,10 @@ pub enum PushTypeKey {
     BashoStartCountdown,
     DayResult,
     BashoResult,
+    HeyaMessage,
+    HeyaPost,
+    HeyaMembership,
+    HeyaStandings,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -261,6 +270,12 @@ pub enum PushType {
     KyujyoAlert(BashoId, RikishiId),
     DayResult(BashoId, PlayerId, Day),
     BashoResult(BashoId, PlayerId),
+    HeyaMessage(HeyaId, String),
+    HeyaPost(HeyaPostId),
+    HeyaAdded(HeyaId, PlayerId),
+    HeyaRemoved(HeyaId, PlayerId),
+    HeyaDissolved(String, PlayerId),
+    HeyaStandings(BashoId, PlayerId, Day),
 }
 
 impl PushType {
@@ -274,6 +289,12 @@ impl PushType {
             }
             PushType::DayResult(_, _, _) => PushTypeKey::DayResult,
             PushType::BashoResult(_, _) => PushTypeKey::BashoResult,
+            PushType::HeyaMessage(_, _) => PushTypeKey::HeyaMessage,
+            PushType::HeyaPost(_) => PushTypeKey::HeyaPost,
+            PushType::HeyaAdded(_, _)
+            | PushType::HeyaRemoved(_, _)
+            | PushType::HeyaDissolved(_, _) => PushTypeKey::HeyaMembership,
+            PushType::HeyaStandings(_, _, _) => PushTypeKey::HeyaStandings,
         }
     }
 
@@ -286,6 +307,12 @@ impl PushType {
             PushType::KyujyoAlert(_, _) => Duration::days(3),
             PushType::DayResult(_, _, _) => Duration::days(1),
             PushType::BashoResult(_, _) => Duration::days(7),
+            PushType::HeyaMessage(_, _) => Duration::days(1),
+            PushType::HeyaPost(_) => Duration::days(1),
+            PushType::HeyaAdded(_, _)
+            | PushType::HeyaRemoved(_, _)
+            | PushType::HeyaDissolved(_, _) => Duration::days(3),
+            PushType::HeyaStandings(_, _, _) => Duration::days(1),
         }
     }
 
@@ -330,7 +357,46 @@ impl PushType {
                     .filter(|s| players.contains(&s.player_id))
                     .collect())
             }
-            PushType::DayResult(_, player_id, _) | PushType::BashoResult(_, player_id) => {
+            PushType::HeyaMessage(heya_id, _) => {
+                let members = db
+                    .prepare(
+                        "
+                        SELECT hp.player_id
+                        FROM heya_player AS hp
+                        JOIN heya ON heya.id = hp.heya_id
+                        WHERE hp.heya_id = ? AND hp.player_id != heya.oyakata_player_id
+                    ",
+                    )?
+                    .query_map(params![heya_id], |row| row.get::<_, PlayerId>(0))?
+                    .collect::<rusqlite::Result<HashSet<_>>>()?;
+                Ok(Subscription::for_type(db, self.key())?
+                    .into_iter()
+                    .filter(|s| members.contains(&s.player_id))
+                    .collect())
+            }
+            PushType::HeyaPost(post_id) => {
+                let members = db
+                    .prepare(
+                        "
+                        SELECT hp.player_id
+                        FROM heya_post AS post
+                        JOIN heya_player AS hp ON hp.heya_id = post.heya_id
+                        WHERE post.id = ? AND hp.player_id != post.player_id
+                    ",
+                    )?
+                    .query_map(params![post_id], |row| row.get::<_, PlayerId>(0))?
+                    .collect::<rusqlite::Result<HashSet<_>>>()?;
+                Ok(Subscription::for_type(db, self.key())?
+                    .into_iter()
+                    .filter(|s| members.contains(&s.player_id))
+                    .collect())
+            }
+            PushType::DayResult(_, player_id, _)
+            | PushType::BashoResult(_, player_id)
+            | PushType::HeyaAdded(_, player_id)
+            | PushType::HeyaRemoved(_, player_id)
+            | PushType::HeyaDissolved(_, player_id)
+            | PushType::HeyaStandings(_, player_id, _) => {
                 Ok(Subscription::for_player(db, *player_id)?
                     .into_iter()
                     .filter(|s| s.opt_in.contains(&self.key()))
@@ -450,14 +516,24 @@ impl PushType {
                         })
                     })?
                     .collect::<rusqlite::Result<Vec<RikishiDayResult>>>()?;
+                let odds = match SimulatedOdds::for_player_day(db, *basho_id, *player_id, *day)? {
+                    Some(odds) if odds.top > 0.0 => format!(
+                        " Cup chance {:.0}%, top {} chance {:.0}%.",
+                        odds.win * 100.0,
+                        TOP_PLACES,
+                        odds.top * 100.0
+                    ),
+                    _ => String::new(),
+                };
                 make_notification(
                     base_url,
                     format!("Day {} Results", day),
                     format!(
-                        "{} now ranked #{}, {} points behind the leader. {}",
+                        "{} now ranked #{}, {} points behind the leader.{} {}",
                         name,
                         rank,
                         leader_score - score,
+                        odds,
                         rikishi
                             .iter()
                             .map(|r| format!(
@@ -538,12 +614,113 @@ impl PushType {
                             awards = awards.iter().join(""),
                             has_have = if awards.len() == 1 { "has" } else {"have"}
                         )
+                    } else if !awards.is_empty() {
+                        format!("{name} finished {basho_id} ranked #{basho_rank} with a score of {score} and earned {awards}. You have been {promoted} to {next_rank:#}.",
+                            awards = awards.iter().map(|a| a.name()).join(" and ")
+                        )
                     } else {
                         format!("{name} finished {basho_id} ranked #{basho_rank} with a score of {score}. You have been {promoted} to {next_rank:#}.")
                     },
                     "basho result",
                 )
             }
+            PushType::HeyaMessage(heya_id, msg) => {
+                let heya = Heya::with_id(db, *heya_id, false)?;
+                make_notification(
+                    base_url,
+                    heya.name,
+                    format!("{}: {}", heya.oyakata.name, msg),
+                    "heya message",
+                )
+            }
+            PushType::HeyaPost(post_id) => {
+                let post =
+                    HeyaPost::with_id(db, *post_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+                let heya = Heya::with_id(db, post.heya_id, false)?;
+                make_notification(
+                    base_url,
+                    match post.basho_id {
+                        Some(basho_id) => format!("{} – {:#}", heya.name, basho_id),
+                        None => heya.name,
+                    },
+                    format!("{}: {}", post.player.name, post.body),
+                    "heya post",
+                )
+            }
+            PushType::HeyaAdded(heya_id, _) => {
+                let heya = Heya::with_id(db, *heya_id, false)?;
+                make_notification(
+                    base_url,
+                    "Welcome to the Heya",
+                    format!("You are now a member of {}.", heya.name),
+                    "heya membership",
+                )
+            }
+            PushType::HeyaRemoved(heya_id, _) => {
+                let heya = Heya::with_id(db, *heya_id, false)?;
+                make_notification(
+                    base_url,
+                    "Heya Membership",
+                    format!("You have been removed from {}.", heya.name),
+                    "heya membership",
+                )
+            }
+            PushType::HeyaStandings(basho_id, player_id, day) => {
+                let league = HeyaStanding::for_basho(db, *basho_id)?;
+                let summaries = Heya::for_player(db, *player_id)?
+                    .iter()
+                    .map(|heya| -> Result<String> {
+                        let (place, entrants): (u32, u32) = db.query_row(
+                            "
+                            SELECT
+                                1 + (
+                                    SELECT COUNT(*)
+                                    FROM heya_player AS hp
+                                    JOIN basho_result AS br2
+                                        ON br2.player_id = hp.player_id AND br2.basho_id = br.basho_id
+                                    WHERE hp.heya_id = :heya_id AND br2.wins > br.wins
+                                ),
+                                (
+                                    SELECT COUNT(*)
+                                    FROM heya_player AS hp
+                                    JOIN basho_result AS br2
+                                        ON br2.player_id = hp.player_id AND br2.basho_id = br.basho_id
+                                    WHERE hp.heya_id = :heya_id
+                                )
+                            FROM basho_result AS br
+                            WHERE br.basho_id = :basho_id AND br.player_id = :player_id
+                        ",
+                            named_params! {
+                                ":heya_id": heya.id,
+                                ":basho_id": basho_id,
+                                ":player_id": player_id,
+                            },
+                            |row| Ok((row.get(0)?, row.get(1)?)),
+                        )?;
+                        let league_place = league
+                            .iter()
+                            .find(|s| s.heya_id == heya.id)
+                            .map(|s| format!(", league #{}", s.ord))
+                            .unwrap_or_default();
+                        Ok(format!(
+                            "{}: you’re #{place} of {entrants}{league_place}",
+                            heya.name
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                make_notification(
+                    base_url,
+                    format!("Day {} Heya Standings", day),
+                    summaries.join(". "),
+                    "heya standings",
+                )
+            }
+            PushType::HeyaDissolved(heya_name, _) => make_notification(
+                base_url,
+                "Heya Dissolved",
+                format!("The oyakata has dissolved {heya_name}."),
+                "heya membership",
+            ),
         };
 
         Ok(payload)
@@ -719,6 +896,69 @@ pub async fn mass_notify_basho_result(
     Ok(total_stats)
 }
 
+pub async fn mass_notify_heya_standings(
+    db_conn: &DbConn,
+    push_builder: &PushBuilder,
+    url: &Url,
+    basho_id: BashoId,
+    day: Day,
+) -> Result<SendStats> {
+    let push_types;
+    {
+        let db = db_conn.lock().unwrap();
+        push_types = db
+            .prepare(
+                "
+            SELECT DISTINCT br.player_id
+            FROM basho_result AS br
+            JOIN heya_player AS hp ON hp.player_id = br.player_id
+            WHERE br.basho_id = ?
+        ",
+            )?
+            .query_map(params![basho_id], |row| {
+                Ok(PushType::HeyaStandings(basho_id, row.get(0)?, day))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+    }
+    info!(
+        "Sending day {} heya standings to {} possible players",
+        day,
+        push_types.len()
+    );
+    let stats = notify_each(db_conn, push_builder, url, &push_types).await?;
+    info!("heya standings notification stats: {:?}", stats);
+    Ok(stats)
+}
+
+/// Sends each notification to the subscriptions it targets, for one-off events that concern a handful of players.
+pub async fn notify_each(
+    db_conn: &DbConn,
+    push_builder: &PushBuilder,
+    url: &Url,
+    push_types: &[PushType],
+) -> Result<SendStats> {
+    let mut total_stats = SendStats::default();
+    for push_type in push_types {
+        let payload;
+        let subscriptions;
+        {
+            let db = db_conn.lock().unwrap();
+            subscriptions = push_type.subscriptions(&db)?;
+            if subscriptions.is_empty() {
+                continue;
+            }
+            payload = push_type.build_payload(url, &db)?;
+        }
+        let results = push_builder
+            .clone()
+            .send(payload, push_type.ttl(), &subscriptions, db_conn)
+            .await?;
+        total_stats.merge(&SendStats::from_results(&results, &subscriptions));
+    }
+    debug!("notification stats: {:?}", total_stats);
+    Ok(total_stats)
+}
+
 #[derive(Debug, Serialize)]
 struct RikishiDayResult {
     name: String,
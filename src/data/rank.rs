@@ -190,6 +190,13 @@ impl FromStr for RankSide {
     }
 }
 
+impl FromSql for RankSide {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        let str = value.as_str()?;
+        str.parse().map_err(|err: RankError| err.into())
+    }
+}
+
 impl ToSql for RankSide {
     fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
         Ok(ToSqlOutput::from(self.to_string()))
@@ -221,6 +228,22 @@ impl RankGroup {
     pub fn as_index(self) -> usize {
         (self.0 - 1) as usize
     }
+
+    pub fn from_index(index: usize) -> Self {
+        Self(index as u8 + 1)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self.0 {
+            1 => "Yokozuna–Ōzeki",
+            2 => "Sekiwake–Komusubi",
+            3 => "Maegashira 1–5",
+            4 => "Maegashira 6–10",
+            5 => "Maegashira 11+",
+            6 => "Jūryō",
+            _ => "Other",
+        }
+    }
 }
 
 impl Deref for RankGroup {
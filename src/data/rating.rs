@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use rusqlite::{Connection, Result as SqlResult, Transaction};
+
+use super::leaders::{assign_ord, Rankable};
+use super::{BashoId, Player, PlayerId, Result};
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+/// New players' ratings move faster until they have this many basho behind them.
+const PROVISIONAL_BASHO: usize = 5;
+const PROVISIONAL_K_MULTIPLIER: f64 = 2.0;
+/// Players need this many rated basho to appear on the rating leaderboard.
+pub const LEADERBOARD_MIN_BASHO: usize = 3;
+
+/// Updates every entrant's rating for one basho, treating it as a round robin where each pair of entrants is a game won by whoever scored more. Entrants from `external_basho_player` without a matching player account count as opponents at the default rating but aren't stored.
+pub fn update_ratings(txn: &Transaction, basho_id: BashoId) -> Result<()> {
+    let prior = prior_ratings(txn, basho_id)?;
+    let entrants = txn
+        .prepare(
+            "
+                SELECT player_id, wins
+                FROM basho_result
+                WHERE basho_id = :basho_id
+
+                UNION ALL
+
+                SELECT p.id, e.wins
+                FROM external_basho_player AS e
+                LEFT JOIN player AS p ON p.name = e.name
+                WHERE e.basho_id = :basho_id
+            ",
+        )?
+        .query_map(named_params! {":basho_id": basho_id}, |row| {
+            Ok((row.get::<_, Option<PlayerId>>(0)?, row.get::<_, u8>(1)?))
+        })?
+        .collect::<SqlResult<Vec<_>>>()?;
+    if entrants.len() < 2 {
+        return Ok(());
+    }
+    info!(
+        "update_ratings for {} entrants in {}",
+        entrants.len(),
+        basho_id
+    );
+
+    let contestants: Vec<Contestant> = entrants
+        .iter()
+        .map(|(player_id, wins)| {
+            let (rating, basho_count) = player_id
+                .and_then(|id| prior.get(&id).copied())
+                .unwrap_or((DEFAULT_RATING, 0));
+            Contestant {
+                rating,
+                basho_count,
+                wins: *wins,
+            }
+        })
+        .collect();
+    let new_ratings = elo_round_robin(&contestants);
+
+    let mut stmt = txn.prepare(
+        "
+            INSERT INTO player_rating (player_id, basho_id, rating)
+            VALUES (?, ?, ?)
+            ON CONFLICT (player_id, basho_id) DO UPDATE
+            SET rating = excluded.rating
+        ",
+    )?;
+    for ((player_id, _), rating) in entrants.iter().zip(new_ratings) {
+        if let Some(player_id) = player_id {
+            stmt.execute(params![player_id, basho_id, rating])?;
+        }
+    }
+    Ok(())
+}
+
+/// Recomputes all ratings from the very first basho, including the historic `external_basho_player` results.
+pub fn backfill_ratings(db: &mut Connection) -> Result<()> {
+    let txn = db.transaction()?;
+    let basho_ids = txn
+        .prepare(
+            "
+                SELECT basho_id FROM basho_result
+                UNION
+                SELECT basho_id FROM external_basho_player
+                ORDER BY basho_id
+            ",
+        )?
+        .query_map(params![], |row| row.get(0))?
+        .collect::<SqlResult<Vec<BashoId>>>()?;
+    info!("backfill_ratings for {} basho", basho_ids.len());
+    txn.execute("DELETE FROM player_rating", params![])?;
+    for basho_id in basho_ids {
+        update_ratings(&txn, basho_id)?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+/// Each player's latest rating before `basho_id` and how many basho it's based on.
+fn prior_ratings(db: &Connection, basho_id: BashoId) -> Result<HashMap<PlayerId, (f64, usize)>> {
+    let mut ratings = HashMap::new();
+    let rows = db
+        .prepare(
+            "
+                SELECT player_id, rating
+                FROM player_rating
+                WHERE basho_id < ?
+                ORDER BY basho_id
+            ",
+        )?
+        .query_map(params![basho_id], |row| {
+            Ok((row.get::<_, PlayerId>(0)?, row.get::<_, f64>(1)?))
+        })?
+        .collect::<SqlResult<Vec<_>>>()?;
+    for (player_id, rating) in rows {
+        let entry = ratings.entry(player_id).or_insert((DEFAULT_RATING, 0));
+        *entry = (rating, entry.1 + 1);
+    }
+    Ok(ratings)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Contestant {
+    rating: f64,
+    basho_count: usize,
+    wins: u8,
+}
+
+/// Multiplayer Elo: each contestant's rating moves by the average over all opponents of the usual head-to-head Elo adjustment.
+fn elo_round_robin(contestants: &[Contestant]) -> Vec<f64> {
+    let opponents = (contestants.len() - 1) as f64;
+    contestants
+        .iter()
+        .enumerate()
+        .map(|(i, me)| {
+            let surprise: f64 = contestants
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, them)| {
+                    let actual = match me.wins.cmp(&them.wins) {
+                        std::cmp::Ordering::Greater => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Less => 0.0,
+                    };
+                    let expected = 1.0 / (1.0 + 10f64.powf((them.rating - me.rating) / 400.0));
+                    actual - expected
+                })
+                .sum();
+            let k = if me.basho_count < PROVISIONAL_BASHO {
+                K_FACTOR * PROVISIONAL_K_MULTIPLIER
+            } else {
+                K_FACTOR
+            };
+            me.rating + k * surprise / opponents
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct PlayerRating {
+    pub player: Player,
+    pub ord: usize,
+    pub rating: f64,
+    pub peak: f64,
+    pub basho_count: usize,
+}
+
+impl Rankable for PlayerRating {
+    fn get_score(&self) -> i32 {
+        self.rating.round() as i32
+    }
+
+    fn set_rank(&mut self, ord: usize) {
+        self.ord = ord;
+    }
+}
+
+impl PlayerRating {
+    pub fn leaderboard(db: &Connection, rank_for_basho: BashoId, limit: u32) -> Result<Vec<Self>> {
+        let mut leaders = db
+            .prepare(
+                "
+                    SELECT
+                        p.*,
+                        pr.rank,
+                        latest.rating,
+                        stats.peak,
+                        stats.basho_count
+                    FROM (
+                        SELECT player_id, MAX(rating) AS peak, COUNT(*) AS basho_count, MAX(basho_id) AS last_basho_id
+                        FROM player_rating
+                        GROUP BY player_id
+                    ) AS stats
+                    JOIN player_rating AS latest
+                        ON latest.player_id = stats.player_id AND latest.basho_id = stats.last_basho_id
+                    JOIN player_info AS p ON p.id = stats.player_id
+                    LEFT JOIN player_rank AS pr ON pr.player_id = p.id AND pr.before_basho_id = ?
+                    WHERE stats.basho_count >= ?
+                    ORDER BY latest.rating DESC
+                    LIMIT ?
+                ",
+            )?
+            .query_map(
+                params![rank_for_basho, LEADERBOARD_MIN_BASHO as u32, limit],
+                |row| {
+                    Ok(Self {
+                        player: Player::from_row(row)?,
+                        ord: 0,
+                        rating: row.get("rating")?,
+                        peak: row.get("peak")?,
+                        basho_count: row.get::<_, u32>("basho_count")? as usize,
+                    })
+                },
+            )?
+            .collect::<SqlResult<Vec<_>>>()?;
+        assign_ord(&mut leaders.iter_mut());
+        Ok(leaders)
+    }
+}
+
+#[derive(Debug)]
+pub struct RatingHistoryPoint {
+    pub basho_id: BashoId,
+    pub rating: f64,
+    pub change: f64,
+}
+
+impl RatingHistoryPoint {
+    /// Newest first.
+    pub fn for_player(db: &Connection, player_id: PlayerId) -> Result<Vec<Self>> {
+        let mut last = DEFAULT_RATING;
+        let mut history = db
+            .prepare(
+                "
+                    SELECT basho_id, rating
+                    FROM player_rating
+                    WHERE player_id = ?
+                    ORDER BY basho_id
+                ",
+            )?
+            .query_map(params![player_id], |row| {
+                Ok((row.get::<_, BashoId>(0)?, row.get::<_, f64>(1)?))
+            })?
+            .collect::<SqlResult<Vec<_>>>()?
+            .into_iter()
+            .map(|(basho_id, rating)| {
+                let change = rating - last;
+                last = rating;
+                Self {
+                    basho_id,
+                    rating,
+                    change,
+                }
+            })
+            .collect::<Vec<_>>();
+        history.reverse();
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contestant(rating: f64, wins: u8) -> Contestant {
+        Contestant {
+            rating,
+            basho_count: PROVISIONAL_BASHO,
+            wins,
+        }
+    }
+
+    #[test]
+    fn winner_gains_loser_loses() {
+        let ratings = elo_round_robin(&[contestant(1500.0, 40), contestant(1500.0, 30)]);
+        assert_eq!(ratings, vec![1516.0, 1484.0]);
+    }
+
+    #[test]
+    fn ratings_are_zero_sum_for_equal_k() {
+        let contestants = [
+            contestant(1600.0, 45),
+            contestant(1500.0, 38),
+            contestant(1450.0, 38),
+            contestant(1400.0, 20),
+        ];
+        let before: f64 = contestants.iter().map(|c| c.rating).sum();
+        let after: f64 = elo_round_robin(&contestants).iter().sum();
+        assert!((before - after).abs() < 1e-9);
+    }
+
+    #[test]
+    fn expected_draw_changes_nothing() {
+        let ratings = elo_round_robin(&[contestant(1500.0, 35), contestant(1500.0, 35)]);
+        assert_eq!(ratings, vec![1500.0, 1500.0]);
+    }
+}
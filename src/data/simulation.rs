@@ -0,0 +1,320 @@
+use rand::random;
+use rusqlite::{Connection, OptionalExtension, Result as SqlResult, Transaction};
+use std::collections::{HashMap, HashSet};
+
+use super::basho::FetchBashoRikishi;
+use super::{BashoId, Day, PlayerId, Rank, RankName, Result, RikishiId};
+
+/// How many times the rest of the basho is played out.
+const SIMULATION_RUNS: usize = 2000;
+/// The "top" in `SimulatedOdds::top`.
+pub const TOP_PLACES: usize = 10;
+/// Weight of the rank-based prior, in bouts, against a rikishi's actual record so far.
+const PRIOR_BOUTS: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimulatedOdds {
+    /// Probability of winning the Emperor's Cup, counting shared wins.
+    pub win: f64,
+    /// Probability of finishing in the top `TOP_PLACES`, counting ties.
+    pub top: f64,
+}
+
+impl SimulatedOdds {
+    pub fn for_basho(db: &Connection, basho_id: BashoId) -> Result<HashMap<PlayerId, Self>> {
+        Ok(db
+            .prepare(
+                "
+                SELECT player_id, win_probability, top_probability
+                FROM basho_simulation
+                WHERE basho_id = :basho_id
+                    AND day = (SELECT MAX(day) FROM basho_simulation WHERE basho_id = :basho_id)
+            ",
+            )?
+            .query_map(named_params! {":basho_id": basho_id}, |row| {
+                Ok((
+                    row.get(0)?,
+                    Self {
+                        win: row.get(1)?,
+                        top: row.get(2)?,
+                    },
+                ))
+            })?
+            .collect::<SqlResult<_>>()?)
+    }
+
+    pub fn for_player_day(
+        db: &Connection,
+        basho_id: BashoId,
+        player_id: PlayerId,
+        day: Day,
+    ) -> Result<Option<Self>> {
+        Ok(db
+            .query_row(
+                "
+                SELECT win_probability, top_probability
+                FROM basho_simulation
+                WHERE basho_id = ? AND player_id = ? AND day = ?
+            ",
+                params![basho_id, player_id, day],
+                |row| {
+                    Ok(Self {
+                        win: row.get(0)?,
+                        top: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+}
+
+/// A rikishi's chance of winning any one of their remaining bouts: a prior based on rank, blended with their record so far.
+fn bout_probability(rank: Rank, wins: u8, losses: u8) -> f64 {
+    let prior = match rank.name {
+        RankName::Yokozuna => 0.70,
+        RankName::Ozeki => 0.62,
+        RankName::Sekiwake | RankName::Komusubi => 0.55,
+        RankName::Maegashira => 0.53 - 0.003 * rank.number as f64,
+        _ => 0.45,
+    };
+    let p = (wins as f64 + PRIOR_BOUTS * prior) / ((wins + losses) as f64 + PRIOR_BOUTS);
+    p.clamp(0.05, 0.95)
+}
+
+/// Chance that a rikishi with bout probability `p_a` beats one with `p_b`, each measured against the field.
+fn head_to_head_probability(p_a: f64, p_b: f64) -> f64 {
+    let a = p_a * (1.0 - p_b);
+    let b = p_b * (1.0 - p_a);
+    a / (a + b)
+}
+
+struct SimRikishi {
+    wins: u8,
+    /// Remaining bouts that haven't been scheduled yet, which are played against the field.
+    unscheduled: u8,
+    p: f64,
+}
+
+/// A scheduled bout between two `SimRikishi`, so exactly one of them wins it in each run.
+struct SimBout {
+    a: usize,
+    b: usize,
+    a_wins: f64,
+}
+
+/// Plays out the rest of the basho `runs` times. `players` lists each player's picks as `SimRikishi` indexes with how much their wins count.
+fn play_out(
+    rikishi: &[SimRikishi],
+    bouts: &[SimBout],
+    players: &[Vec<(usize, u8)>],
+    runs: usize,
+) -> Vec<SimulatedOdds> {
+    let mut win_counts = vec![0u32; players.len()];
+    let mut top_counts = vec![0u32; players.len()];
+    let mut rikishi_wins = vec![0u8; rikishi.len()];
+    let mut totals = vec![0u8; players.len()];
+    let mut sorted_totals = vec![0u8; players.len()];
+    for _ in 0..runs {
+        for (wins, r) in rikishi_wins.iter_mut().zip(rikishi) {
+            *wins = r.wins + (0..r.unscheduled).filter(|_| random::<f64>() < r.p).count() as u8;
+        }
+        for bout in bouts {
+            if random::<f64>() < bout.a_wins {
+                rikishi_wins[bout.a] += 1;
+            } else {
+                rikishi_wins[bout.b] += 1;
+            }
+        }
+        for (total, indexes) in totals.iter_mut().zip(players) {
+            *total = indexes
+                .iter()
+                .map(|(i, weight)| rikishi_wins[*i] * weight)
+                .sum();
+        }
+        sorted_totals.copy_from_slice(&totals);
+        sorted_totals.sort_unstable_by(|a, b| b.cmp(a));
+        let best = sorted_totals[0];
+        let top_cut = sorted_totals[TOP_PLACES.min(sorted_totals.len()) - 1];
+        for (i, total) in totals.iter().enumerate() {
+            if *total == best {
+                win_counts[i] += 1;
+            }
+            if *total >= top_cut {
+                top_counts[i] += 1;
+            }
+        }
+    }
+    win_counts
+        .into_iter()
+        .zip(top_counts)
+        .map(|(win, top)| SimulatedOdds {
+            win: win as f64 / runs as f64,
+            top: top as f64 / runs as f64,
+        })
+        .collect()
+}
+
+/// Plays out the remaining days of the basho `SIMULATION_RUNS` times from the results through `day` and stores every entrant's odds for that day.
+pub fn simulate_basho(txn: &Transaction, basho_id: BashoId, day: Day) -> Result<()> {
+    let rikishi = FetchBashoRikishi::with_db(txn, basho_id, &HashSet::new())?.by_id;
+    // Each pick with how much its wins count, which is double for a captain.
+    let mut picks: HashMap<PlayerId, Vec<(RikishiId, u8)>> = HashMap::new();
+    {
+        let mut stmt = txn.prepare(
+            "
+                SELECT
+                    pick.player_id,
+                    pick.rikishi_id,
+                    1 + (pick.is_captain AND basho.captain_picks) AS weight
+                FROM pick
+                JOIN basho ON basho.id = pick.basho_id
+                WHERE pick.basho_id = ?
+            ",
+        )?;
+        let rows = stmt.query_map(params![basho_id], |row| {
+            Ok((
+                row.get::<_, PlayerId>(0)?,
+                row.get::<_, RikishiId>(1)?,
+                row.get::<_, u8>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (player_id, rikishi_id, weight) = row?;
+            picks
+                .entry(player_id)
+                .or_default()
+                .push((rikishi_id, weight));
+        }
+    }
+    if picks.is_empty() {
+        return Ok(());
+    }
+
+    // Bouts already announced for the coming days are played as pairs; the rest of each rikishi's bouts are against the field.
+    let mut scheduled: HashMap<(Day, u16), Vec<RikishiId>> = HashMap::new();
+    {
+        let mut stmt = txn.prepare(
+            "
+                SELECT day, seq, rikishi_id
+                FROM torikumi
+                WHERE basho_id = ? AND day > ? AND win IS NULL
+            ",
+        )?;
+        let rows = stmt.query_map(params![basho_id, day], |row| {
+            Ok((
+                row.get::<_, Day>(0)?,
+                row.get::<_, u16>(1)?,
+                row.get::<_, RikishiId>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (day, seq, rikishi_id) = row?;
+            scheduled.entry((day, seq)).or_default().push(rikishi_id);
+        }
+    }
+    let mut scheduled_count: HashMap<RikishiId, u8> = HashMap::new();
+    for id in scheduled.values().flatten() {
+        *scheduled_count.entry(*id).or_default() += 1;
+    }
+
+    let picked: HashSet<RikishiId> = picks.values().flatten().map(|(id, _)| *id).collect();
+    // Index only the picked rikishi and their scheduled opponents so each run samples them once.
+    let mut sim_rikishi: Vec<SimRikishi> = vec![];
+    let mut index_by_id: HashMap<RikishiId, usize> = HashMap::new();
+    let mut index_of = |id: RikishiId| -> Option<usize> {
+        let r = rikishi.get(&id)?;
+        Some(*index_by_id.entry(id).or_insert_with(|| {
+            let scheduled = scheduled_count.get(&id).copied().unwrap_or(0);
+            sim_rikishi.push(SimRikishi {
+                wins: r.wins,
+                unscheduled: r.remaining_days().saturating_sub(scheduled),
+                p: bout_probability(r.rank, r.wins, r.losses),
+            });
+            sim_rikishi.len() - 1
+        }))
+    };
+    let (player_ids, players): (Vec<PlayerId>, Vec<Vec<(usize, u8)>>) = picks
+        .into_iter()
+        .map(|(player_id, rikishi_ids)| {
+            let indexes = rikishi_ids
+                .into_iter()
+                .filter_map(|(id, weight)| Some((index_of(id)?, weight)))
+                .collect();
+            (player_id, indexes)
+        })
+        .unzip();
+    let bouts: Vec<(usize, usize)> = scheduled
+        .into_values()
+        .filter(|ids| ids.iter().any(|id| picked.contains(id)))
+        .filter_map(|ids| match ids[..] {
+            [a, b] => Some((index_of(a)?, index_of(b)?)),
+            _ => None,
+        })
+        .collect();
+    let bouts: Vec<SimBout> = bouts
+        .into_iter()
+        .map(|(a, b)| SimBout {
+            a,
+            b,
+            a_wins: head_to_head_probability(sim_rikishi[a].p, sim_rikishi[b].p),
+        })
+        .collect();
+    debug!(
+        "simulating {} for {} players with {} rikishi and {} scheduled bouts",
+        basho_id,
+        players.len(),
+        sim_rikishi.len(),
+        bouts.len()
+    );
+    let odds = play_out(&sim_rikishi, &bouts, &players, SIMULATION_RUNS);
+
+    txn.execute(
+        "DELETE FROM basho_simulation WHERE basho_id = ? AND day = ?",
+        params![basho_id, day],
+    )?;
+    let mut insert = txn.prepare(
+        "
+            INSERT INTO basho_simulation (basho_id, day, player_id, win_probability, top_probability)
+            VALUES (?, ?, ?, ?, ?)
+        ",
+    )?;
+    for (player_id, odds) in player_ids.iter().zip(odds) {
+        insert.execute(params![basho_id, day, player_id, odds.win, odds.top])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn record_pulls_probability_away_from_rank_prior() {
+        let rank = Rank::from_str("M8e").unwrap();
+        let fresh = bout_probability(rank, 0, 0);
+        assert!(bout_probability(rank, 7, 0) > fresh);
+        assert!(bout_probability(rank, 0, 7) < fresh);
+        assert!(bout_probability(Rank::from_str("Y1e").unwrap(), 0, 0) > fresh);
+    }
+
+    #[test]
+    fn scheduled_bouts_have_exactly_one_winner() {
+        let rikishi = |wins, unscheduled| SimRikishi {
+            wins,
+            unscheduled,
+            p: 0.5,
+        };
+        let rikishi = [rikishi(12, 0), rikishi(8, 0), rikishi(9, 0), rikishi(9, 0)];
+        // Rikishi 2 and 3 meet on the last day, so the second player finishes on 19 every time and the first has clinched on 20.
+        let bouts = [SimBout {
+            a: 2,
+            b: 3,
+            a_wins: 0.5,
+        }];
+        let players = [vec![(0, 1), (1, 1)], vec![(2, 1), (3, 1)]];
+        let odds = play_out(&rikishi, &bouts, &players, 200);
+        assert_eq!(1.0, odds[0].win);
+        assert_eq!(0.0, odds[1].win);
+    }
+}
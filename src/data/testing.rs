@@ -0,0 +1,88 @@
+//! Fixtures for tests that need a database.
+
+use chrono::{DateTime, Utc};
+use rusqlite::config::DbConfig::SQLITE_DBCONFIG_ENABLE_FKEY;
+use rusqlite::{Connection, OpenFlags};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{BashoId, PlayerId, Rank, RikishiId};
+
+/// An in-memory database for tests: the schema of `sql/blank.sqlite`, which is current through `20210103-kyujo.sql`, plus every migration after that.
+pub fn test_db() -> Connection {
+    let sql_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("sql");
+    let conn = Connection::open_in_memory().expect("open in-memory db");
+    conn.set_db_config(SQLITE_DBCONFIG_ENABLE_FKEY, true)
+        .expect("set foreign key enforcement to on");
+    let blank = Connection::open_with_flags(
+        sql_dir.join("blank.sqlite"),
+        OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )
+    .expect("open blank.sqlite");
+    let schema = blank
+        .prepare(
+            "
+                SELECT sql
+                FROM sqlite_master
+                WHERE sql IS NOT NULL AND name != 'sqlite_sequence'
+                ORDER BY rowid
+            ",
+        )
+        .and_then(|mut stmt| {
+            let schema = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>();
+            schema
+        })
+        .expect("read blank.sqlite schema");
+    for sql in schema {
+        conn.execute_batch(&sql).expect("create blank schema");
+    }
+    let mut migrations = std::fs::read_dir(&sql_dir)
+        .expect("list sql dir")
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| {
+            name.starts_with("20") && name.ends_with(".sql") && name.as_str() > "20210103-kyujo.sql"
+        })
+        .collect::<Vec<_>>();
+    migrations.sort();
+    for name in migrations {
+        let sql = std::fs::read_to_string(sql_dir.join(&name)).expect("read migration");
+        conn.execute_batch(&sql)
+            .unwrap_or_else(|e| panic!("apply {}: {}", name, e));
+    }
+    conn
+}
+
+pub fn insert_player(db: &Connection, id: PlayerId, name: &str) {
+    db.execute(
+        "INSERT INTO player (id, join_date, name) VALUES (?, ?, ?)",
+        params![id, Utc::now(), name],
+    )
+    .unwrap();
+}
+
+pub fn insert_basho(db: &Connection, basho_id: BashoId, start_date: DateTime<Utc>) {
+    db.execute(
+        "INSERT INTO basho (id, start_date, venue) VALUES (?, ?, 'Tokyo')",
+        params![basho_id, start_date],
+    )
+    .unwrap();
+}
+
+/// Adds the rikishi if they're new and puts them on the basho's banzuke at `rank`, e.g. "M3e".
+pub fn insert_banzuke(db: &Connection, basho_id: BashoId, id: RikishiId, name: &str, rank: &str) {
+    db.execute(
+        "INSERT OR IGNORE INTO rikishi (id, family_name, given_name) VALUES (?, ?, '')",
+        params![id, name],
+    )
+    .unwrap();
+    db.execute(
+        "
+            INSERT INTO banzuke (rikishi_id, basho_id, family_name, given_name, rank)
+            VALUES (?, ?, ?, '', ?)
+        ",
+        params![id, basho_id, name, Rank::from_str(rank).unwrap()],
+    )
+    .unwrap();
+}
@@ -15,7 +15,7 @@ use self::tests::BashoInfo;
 use crate::data::BashoInfo;
 use crate::data::DbConn;
 use crate::data::{
-    basho::{update_torikumi, TorikumiMatchUpdateData},
+    basho::{schedule_torikumi, update_torikumi, TorikumiMatchUpdateData},
     BashoId, Rank, RankDivision,
 };
 use crate::Config;
@@ -137,6 +137,32 @@ impl BanzukeResponse {
         out
     }
 
+    /// Bouts announced for the day but not yet fought, as pairs of shikona.
+    fn torikumi_schedule_data(&self, day: u8) -> Vec<(String, String)> {
+        let day_idx = day as usize - 1;
+        assert!(day_idx < 15);
+        let mut seen_rikishi = HashSet::new();
+        let mut out = vec![];
+        for rikishi in self.all_rikishi() {
+            let Some(torikumi) = rikishi.record.get(day_idx) else {
+                continue;
+            };
+            if torikumi.result != BoutResult::None
+                || torikumi.opponent_shikona_en.is_empty()
+                || seen_rikishi.contains(&rikishi.shikona_en)
+            {
+                continue;
+            }
+            seen_rikishi.insert(&rikishi.shikona_en);
+            seen_rikishi.insert(&torikumi.opponent_shikona_en);
+            out.push((
+                rikishi.shikona_en.to_owned(),
+                torikumi.opponent_shikona_en.to_owned(),
+            ));
+        }
+        out
+    }
+
     pub fn all_rikishi(&self) -> impl Iterator<Item = &RikishiResponse> {
         self.east.iter().chain(self.west.iter())
     }
@@ -169,6 +195,40 @@ pub async fn query_and_update_sumo_api_torikumi(
     Ok(complete)
 }
 
+/// Stores the announced bouts for an upcoming day so players can predict them. Returns how many bouts were scheduled.
+pub async fn query_and_schedule_sumo_api_torikumi(
+    basho_id: BashoId,
+    day: u8,
+    db_conn: &DbConn,
+) -> anyhow::Result<usize> {
+    debug!(
+        "Querying sumo-api for basho {} day {} schedule",
+        basho_id.id(),
+        day
+    );
+    let resp = BanzukeResponse::fetch(basho_id, RankDivision::Makuuchi).await?;
+    let bouts = resp.torikumi_schedule_data(day);
+    info!("Got day {} schedule with {} bouts", day, bouts.len());
+    if bouts.is_empty() {
+        return Ok(0);
+    }
+
+    if *DRY_RUN {
+        info!("Dry run; not updating db");
+        for (a, b) in &bouts {
+            debug!("{} vs {}", a, b);
+        }
+        return Ok(0);
+    }
+
+    Ok(schedule_torikumi(
+        &mut db_conn.lock().unwrap(),
+        basho_id,
+        day,
+        &bouts,
+    )?)
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WebhookData {
@@ -575,6 +635,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn schedule_data_skips_fought_bouts() {
+        init_logger();
+
+        let resp: BanzukeResponse =
+            serde_json::from_str(BANZUKE_202307).expect("parse API response fixture");
+        assert!(resp.torikumi_schedule_data(4).is_empty());
+    }
+
     #[test]
     fn verify_webhook_signature() {
         assert!(
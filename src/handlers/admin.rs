@@ -1,9 +1,14 @@
 use super::{BaseTemplate, HandlerError, Result};
 use crate::data::basho::backfill_past_player_ranks;
+use crate::data::over_under;
 use crate::data::push::{
-    mass_notify_basho_result, mass_notify_day_result, mass_notify_kyujyo, SendStats,
+    mass_notify_basho_result, mass_notify_day_result, mass_notify_heya_standings,
+    mass_notify_kyujyo, SendStats,
+};
+use crate::data::rating::backfill_ratings;
+use crate::data::{
+    self, basho, Award, BashoId, DataError, DbConn, Player, PlayerId, Rank, RikishiId,
 };
-use crate::data::{self, basho, BashoId, DbConn, Player, PlayerId, Rank};
 use crate::external::discord::DiscordAuthProvider;
 use crate::external::google::GoogleAuthProvider;
 use crate::external::reddit::RedditAuthProvider;
@@ -69,6 +74,10 @@ pub struct BashoData {
     venue: String,
     #[serde(deserialize_with = "deserialize_datetime")]
     start_date: NaiveDateTime,
+    #[serde(default)]
+    sealed_picks: bool,
+    #[serde(default)]
+    captain_picks: bool,
     banzuke: Vec<BanzukeRikishi>,
     notify_kyujyo: bool,
 }
@@ -81,7 +90,9 @@ impl BashoData {
                 "
             SELECT
                 basho.start_date,
-                basho.venue
+                basho.venue,
+                basho.sealed_picks,
+                basho.captain_picks
             FROM basho
             WHERE basho.id = ?",
                 params![id],
@@ -91,6 +102,8 @@ impl BashoData {
                     Ok(Self {
                         start_date,
                         venue: row.get("venue")?,
+                        sealed_picks: row.get("sealed_picks")?,
+                        captain_picks: row.get("captain_picks")?,
                         banzuke: Self::fetch_banzuke_from_db(&db, id)?,
                         notify_kyujyo: true,
                     })
@@ -117,6 +130,8 @@ impl BashoData {
         Self {
             venue: id.expected_venue(),
             start_date: id.expected_start_date().naive_local(),
+            sealed_picks: false,
+            captain_picks: false,
             banzuke: vec![],
             notify_kyujyo: false,
         }
@@ -197,6 +212,8 @@ pub async fn edit_basho_post(
             basho_id,
             &basho.venue,
             &basho.start_date,
+            basho.sealed_picks,
+            basho.captain_picks,
             &basho
                 .banzuke
                 .iter()
@@ -298,13 +315,48 @@ pub async fn torikumi_post(
     }
 
     let stats = if torikumi.notify {
-        mass_notify_day_result(&state.db, &state.push, &state.config.url(), path.0, path.1).await?
+        let mut stats =
+            mass_notify_day_result(&state.db, &state.push, &state.config.url(), path.0, path.1)
+                .await?;
+        stats.merge(
+            &mass_notify_heya_standings(
+                &state.db,
+                &state.push,
+                &state.config.url(),
+                path.0,
+                path.1,
+            )
+            .await?,
+        );
+        stats
     } else {
         SendStats::default()
     };
     Ok(web::Json(stats))
 }
 
+#[post("/day/{day}/schedule")]
+pub async fn schedule_torikumi(
+    path: web::Path<(BashoId, u8)>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    BaseTemplate::for_admin(&state.db.lock().unwrap(), &identity, &state)?;
+    if !(1..=15).contains(&path.1) {
+        return Err(HandlerError::NotFound("day".to_string()));
+    }
+    let count = sumo_api::query_and_schedule_sumo_api_torikumi(path.0, path.1, &state.db).await?;
+    if count == 0 {
+        warn!("no scheduled bouts from sumo-api for day {}", path.1);
+    }
+    Ok(HttpResponse::SeeOther()
+        .insert_header((
+            http::header::LOCATION,
+            format!("{}/predictions", path.0.url_path()),
+        ))
+        .finish())
+}
+
 #[post("/finalize")]
 pub async fn finalize_basho(
     path: web::Path<BashoId>,
@@ -321,6 +373,95 @@ pub async fn finalize_basho(
     Ok(web::Json(stats))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct YushoData {
+    rikishi_id: RikishiId,
+}
+
+#[post("/yusho")]
+pub async fn record_yusho(
+    path: web::Path<BashoId>,
+    form: web::Form<YushoData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let mut db = state.db.lock().unwrap();
+    BaseTemplate::for_admin(&db, &identity, &state)?;
+    basho::record_yusho_winner(&mut db, *path, form.rikishi_id)?;
+    Ok(HttpResponse::SeeOther()
+        .insert_header((http::header::LOCATION, path.url_path()))
+        .finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WinLineData {
+    defaults: Option<String>,
+    rikishi_id: Option<RikishiId>,
+    line: Option<u8>,
+    remove: Option<String>,
+}
+
+#[post("/over-under/lines")]
+pub async fn over_under_lines(
+    path: web::Path<BashoId>,
+    form: web::Form<WinLineData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let mut db = state.db.lock().unwrap();
+    BaseTemplate::for_admin(&db, &identity, &state)?;
+    if form.defaults.is_some() {
+        let count = over_under::feature_defaults(&mut db, *path)?;
+        info!("featured {} rikishi for over/under in {}", count, *path);
+    }
+    if let Some(rikishi_id) = form.rikishi_id {
+        let line = if form.remove.is_some() {
+            None
+        } else {
+            Some(form.line.ok_or(DataError::InvalidPicks)?)
+        };
+        over_under::set_line(&db, *path, rikishi_id, line)?;
+    }
+    Ok(HttpResponse::SeeOther()
+        .insert_header((
+            http::header::LOCATION,
+            format!("{}/over-under", path.url_path()),
+        ))
+        .finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AwardData {
+    award: Award,
+    player_id: PlayerId,
+}
+
+#[post("/bestow_award")]
+pub async fn bestow_award(
+    path: web::Path<BashoId>,
+    json: web::Json<AwardData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let mut db = state.db.lock().unwrap();
+    BaseTemplate::for_admin(&db, &identity, &state)?;
+    json.award.bestow(&mut db, *path, json.player_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/revoke_award")]
+pub async fn revoke_award(
+    path: web::Path<BashoId>,
+    json: web::Json<AwardData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let mut db = state.db.lock().unwrap();
+    BaseTemplate::for_admin(&db, &identity, &state)?;
+    json.award.revoke(&mut db, *path, json.player_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
 #[post("/backfill_player_ranks")]
 pub async fn backfill_player_ranks(
     path: web::Path<BashoId>,
@@ -330,6 +471,7 @@ pub async fn backfill_player_ranks(
     let mut db = state.db.lock().unwrap();
     BaseTemplate::for_admin(&db, &identity, &state)?;
     backfill_past_player_ranks(&mut db, *path)?;
+    backfill_ratings(&mut db)?;
     Ok(HttpResponse::SeeOther()
         .insert_header((http::header::LOCATION, &*path.url_path()))
         .finish())
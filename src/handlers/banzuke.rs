@@ -0,0 +1,51 @@
+use actix_identity::Identity;
+use actix_web::{get, web};
+use askama::Template;
+use askama_web::WebTemplate;
+
+use super::{BaseTemplate, HandlerError, Result};
+use crate::data::leaders::{BanzukeEntry, BanzukeRankGroup, PlayerRanking};
+use crate::data::BashoId;
+use crate::AppState;
+
+#[derive(Template, WebTemplate)]
+#[template(path = "banzuke.html")]
+pub struct BanzukeTemplate {
+    base: BaseTemplate,
+    basho_id: BashoId,
+    groups: Vec<BanzukeRankGroup>,
+    prev_basho_id: Option<BashoId>,
+    next_basho_id: Option<BashoId>,
+}
+
+impl BanzukeTemplate {
+    fn is_self(&self, entry: &BanzukeEntry) -> bool {
+        self.base
+            .player
+            .as_ref()
+            .is_some_and(|p| p.id == entry.ranking.player.id)
+    }
+}
+
+#[get("/banzuke/{basho_id}")]
+pub async fn banzuke_page(
+    path: web::Path<BashoId>,
+    state: web::Data<AppState>,
+    identity: Option<Identity>,
+) -> Result<BanzukeTemplate> {
+    let basho_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let groups = BanzukeRankGroup::for_basho(&db, basho_id)?;
+    if groups.is_empty() {
+        return Err(HandlerError::NotFound("banzuke".to_string()));
+    }
+    let prev_basho_id = basho_id.incr(-1);
+    let next_basho_id = basho_id.next();
+    Ok(BanzukeTemplate {
+        base: BaseTemplate::new(&db, identity.as_ref(), &state)?,
+        basho_id,
+        groups,
+        prev_basho_id: PlayerRanking::has_banzuke(&db, prev_basho_id)?.then_some(prev_basho_id),
+        next_basho_id: PlayerRanking::has_banzuke(&db, next_basho_id)?.then_some(next_basho_id),
+    })
+}
@@ -0,0 +1,106 @@
+use actix_identity::Identity;
+use actix_web::{get, http, post, web, HttpResponse, Responder};
+use askama::Template;
+use askama_web::WebTemplate;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{BaseTemplate, IdentityExt, Result};
+use crate::data::banzuke_prediction::{self, BanzukePredictionStanding, Candidate, PredictedRank};
+use crate::data::{BashoId, DataError, Rank, RikishiId};
+use crate::AppState;
+
+#[derive(Template, WebTemplate)]
+#[template(path = "banzuke_prediction.html")]
+pub struct BanzukePredictionTemplate {
+    base: BaseTemplate,
+    basho_id: BashoId,
+    is_open: bool,
+    candidates: Vec<Candidate>,
+    /// Each rank to fill, with the viewing player's saved choice or else the rikishi who held it last basho.
+    slots: Vec<(Rank, RikishiId)>,
+    has_saved: bool,
+    entrant_count: u32,
+    /// The viewing player's scored prediction.
+    predicted: Vec<PredictedRank>,
+    standings: Vec<BanzukePredictionStanding>,
+}
+
+impl BanzukePredictionTemplate {
+    fn is_self(&self, standing: &BanzukePredictionStanding) -> bool {
+        self.base
+            .player
+            .as_ref()
+            .is_some_and(|p| p.id == standing.player.id)
+    }
+}
+
+#[get("/banzuke-prediction")]
+pub async fn prediction_page(
+    path: web::Path<BashoId>,
+    state: web::Data<AppState>,
+    identity: Option<Identity>,
+) -> Result<BanzukePredictionTemplate> {
+    let basho_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
+    let is_open = banzuke_prediction::is_open(&db, basho_id)?;
+    let predicted = match &base.player {
+        Some(player) => PredictedRank::for_player(&db, player.id, basho_id)?,
+        None => vec![],
+    };
+    let (candidates, slots) = if is_open {
+        let candidates = Candidate::for_basho(&db, basho_id)?;
+        let saved: BTreeMap<Rank, RikishiId> = predicted
+            .iter()
+            .map(|p| (p.predicted, p.rikishi_id))
+            .collect();
+        let slots = candidates
+            .iter()
+            .filter(|c| c.rank.is_makuuchi())
+            .map(|c| (c.rank, saved.get(&c.rank).copied().unwrap_or(c.id)))
+            .collect();
+        (candidates, slots)
+    } else {
+        (vec![], vec![])
+    };
+    Ok(BanzukePredictionTemplate {
+        is_open,
+        candidates,
+        slots,
+        has_saved: !predicted.is_empty(),
+        entrant_count: BanzukePredictionStanding::entrant_count(&db, basho_id)?,
+        predicted: if is_open { vec![] } else { predicted },
+        standings: BanzukePredictionStanding::for_basho(&db, basho_id)?,
+        base,
+        basho_id,
+    })
+}
+
+/// Form fields are `rank-{rank}`, such as `rank-M3w`, with the predicted rikishi id as the value.
+#[post("/banzuke-prediction")]
+pub async fn save_prediction(
+    path: web::Path<BashoId>,
+    form: web::Form<HashMap<String, RikishiId>>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let basho_id = path.into_inner();
+    let player_id = identity.player_id()?;
+    let prediction = form
+        .iter()
+        .filter_map(|(key, id)| key.strip_prefix("rank-").map(|rank| (rank, *id)))
+        .map(|(rank, id)| {
+            rank.parse::<Rank>()
+                .map(|rank| (rank, id))
+                .map_err(|_| DataError::InvalidPicks)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut db = state.db.lock().unwrap();
+    banzuke_prediction::save_prediction(&mut db, player_id, basho_id, &prediction)?;
+    Ok(HttpResponse::SeeOther()
+        .insert_header((
+            http::header::LOCATION,
+            format!("{}/banzuke-prediction", basho_id.url_path()),
+        ))
+        .finish())
+}
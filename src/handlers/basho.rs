@@ -5,11 +5,13 @@ use rusqlite::Connection;
 use std::collections::HashSet;
 
 use super::{BaseTemplate, HandlerError, IdentityExt, Result};
-use crate::data::heya::HeyaId;
+use crate::data::basho::LastPicks;
+use crate::data::heya::{HeyaId, HeyaStanding};
 use crate::data::leaders::{BashoPlayerResults, ResultPlayer};
+use crate::data::pick_history::{PickVersion, PopularityTrend};
 use crate::data::{
-    self, BashoId, BashoInfo, BashoRikishiByRank, DataError, FetchBashoRikishi, Heya, PlayerId,
-    RankGroup, RankSide, RikishiId,
+    self, BashoId, BashoInfo, BashoRikishi, BashoRikishiByRank, DataError, FetchBashoRikishi, Heya,
+    Player, PlayerId, RankGroup, RankSide, RikishiId,
 };
 use crate::AppState;
 
@@ -22,11 +24,15 @@ pub struct BashoTemplate {
     base: BaseTemplate,
     basho: BashoInfo,
     heya: Option<Heya>,
+    heya_standings: Vec<HeyaStanding>,
     leaders: Vec<BashoPlayerResults>,
     self_leader_index: Option<usize>,
     rikishi_by_rank: Vec<BashoRikishiByRank>,
     next_day: u8,
     initially_selectable: bool,
+    last_picks: Option<LastPicks>,
+    captain_pick: Option<RikishiId>,
+    yusho_pick: Option<RikishiId>,
 }
 
 pub struct HeyaOption<'a> {
@@ -46,6 +52,45 @@ impl BashoTemplate {
         })
     }
 
+    /// Index into `leaders` of the last player who can still catch the basho leader, if anyone listed can't.
+    fn contention_cut_index(&self) -> Option<usize> {
+        let is_ranked = |l: &BashoPlayerResults| matches!(l.player, ResultPlayer::RankedPlayer(..));
+        if !self.leaders.iter().any(|l| is_ranked(l) && l.is_eliminated) {
+            return None;
+        }
+        self.leaders
+            .iter()
+            .rposition(|l| is_ranked(l) && !l.is_eliminated)
+    }
+
+    /// Every rikishi on the banzuke who isn't kyujyo, in rank order.
+    fn yusho_candidates(&self) -> Vec<&BashoRikishi> {
+        self.rikishi_by_rank
+            .iter()
+            .flat_map(|rr| [rr.east.as_ref(), rr.west.as_ref()])
+            .flatten()
+            .filter(|r| !r.is_kyujyo)
+            .collect()
+    }
+
+    fn player_picks(&self) -> Vec<&BashoRikishi> {
+        self.rikishi_by_rank
+            .iter()
+            .flat_map(|rr| [rr.east.as_ref(), rr.west.as_ref()])
+            .flatten()
+            .filter(|r| r.is_player_pick)
+            .collect()
+    }
+
+    fn rikishi_name(&self, rikishi_id: RikishiId) -> Option<&str> {
+        self.rikishi_by_rank
+            .iter()
+            .flat_map(|rr| [rr.east.as_ref(), rr.west.as_ref()])
+            .flatten()
+            .find(|r| r.id == rikishi_id)
+            .map(|r| r.name.as_str())
+    }
+
     fn heya_options(&self) -> Option<Vec<HeyaOption<'_>>> {
         let selected_heya_id = self.heya.as_ref().map_or(-1, |h| h.id);
         self.base.player.as_ref().map(|player| {
@@ -93,10 +138,14 @@ pub async fn basho(
     let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
     let player_id = base.player.as_ref().map(|p| p.id);
     let picks = fetch_player_picks(&db, player_id, basho_id)?;
+    let mut fetched_rikishi = FetchBashoRikishi::with_db(&db, basho_id, &picks)?;
+    if basho.picks_are_sealed() {
+        fetched_rikishi.hide_pick_counts();
+    }
     let FetchBashoRikishi {
         by_id: rikishi_by_id,
         by_rank: rikishi_by_rank,
-    } = FetchBashoRikishi::with_db(&db, basho_id, &picks)?;
+    } = fetched_rikishi;
     let limit = if !basho.has_started() || query.all.unwrap_or(false) {
         1000000
     } else {
@@ -124,10 +173,27 @@ pub async fn basho(
         heya.as_ref().map(|h| h.id),
     )?;
     let self_leader_index = leaders.iter().position(|l| l.is_self);
+    let last_picks = match player_id {
+        Some(player_id) if !basho.has_started() => LastPicks::for_player(&db, player_id, basho_id)?,
+        _ => None,
+    };
+    let (captain_pick, yusho_pick) = match player_id {
+        Some(player_id) => (
+            data::basho::captain_pick_for_player(&db, player_id, basho_id)?,
+            data::basho::yusho_pick_for_player(&db, player_id, basho_id)?,
+        ),
+        None => (None, None),
+    };
+    let heya_standings = if basho.has_started() {
+        HeyaStanding::for_basho(&db, basho_id)?
+    } else {
+        vec![]
+    };
     Ok(Either::Left(BashoTemplate {
         leaders,
         self_leader_index,
         heya,
+        heya_standings,
         next_day: rikishi_by_rank
             .iter()
             .map(|rr| rr.next_day())
@@ -137,6 +203,9 @@ pub async fn basho(
         initially_selectable: !basho.has_started()
             && base.player.is_some()
             && picks.len() < RankGroup::count(),
+        last_picks,
+        captain_pick,
+        yusho_pick,
         basho,
         base,
     }))
@@ -179,6 +248,8 @@ pub struct SavePicksFormData {
     rank_group_3: Option<RikishiId>,
     rank_group_4: Option<RikishiId>,
     rank_group_5: Option<RikishiId>,
+    captain: Option<RikishiId>,
+    yusho: Option<RikishiId>,
 }
 
 #[post("/picks")]
@@ -197,8 +268,119 @@ pub async fn save_picks(
         form.rank_group_5,
     ];
     let mut db = state.db.lock().unwrap();
-    match data::basho::save_player_picks(&mut db, player_id, path.into_inner(), *picks) {
+    match data::basho::save_player_picks(
+        &mut db,
+        player_id,
+        path.into_inner(),
+        *picks,
+        form.captain,
+        form.yusho,
+    ) {
         Ok(_) => Ok(HttpResponse::Ok()),
         Err(e) => Err(e.into()),
     }
 }
+
+/// How many rikishi to show in the pick popularity trend.
+const POPULARITY_TREND_LIMIT: usize = 10;
+
+#[derive(Template, WebTemplate)]
+#[template(path = "pick_history.html")]
+pub struct PickHistoryTemplate {
+    base: BaseTemplate,
+    basho: BashoInfo,
+    player: Player,
+    versions: Vec<PickVersion>,
+    /// Only shown once the basho has started, since pick counts are hidden until then.
+    trend: Option<PopularityTrend>,
+}
+
+impl PickHistoryTemplate {
+    fn is_before_deadline(&self, version: &PickVersion) -> Option<bool> {
+        version
+            .save_date
+            .map(|date| date <= self.basho.pick_deadline())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PickHistoryQuery {
+    /// Another player's name, for admins.
+    player: Option<String>,
+}
+
+#[get("/picks/history")]
+pub async fn pick_history(
+    path: web::Path<BashoId>,
+    query: web::Query<PickHistoryQuery>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<PickHistoryTemplate> {
+    let basho_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let basho = BashoInfo::with_id(&db, basho_id)?
+        .ok_or_else(|| HandlerError::NotFound("basho".to_string()))?;
+    let (base, player) = match &query.player {
+        Some(name) => {
+            let base = BaseTemplate::for_admin(&db, &identity, &state)?;
+            let player = Player::with_name(&db, name.to_owned(), basho_id)?
+                .ok_or_else(|| HandlerError::NotFound("player".to_string()))?;
+            (base, player)
+        }
+        None => {
+            let base = BaseTemplate::new(&db, Some(&identity), &state)?;
+            let player = Player::with_id(&db, identity.player_id()?, basho_id)?
+                .ok_or(HandlerError::MustBeLoggedIn)?;
+            (base, player)
+        }
+    };
+    let versions = PickVersion::for_player(&db, player.id, basho_id)?;
+    let trend = if basho.has_started() {
+        Some(PopularityTrend::for_basho(
+            &db,
+            basho_id,
+            basho.pick_deadline(),
+            POPULARITY_TREND_LIMIT,
+        )?)
+    } else {
+        None
+    };
+    Ok(PickHistoryTemplate {
+        base,
+        basho,
+        player,
+        versions,
+        trend,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct RestorePicksFormData {
+    player_id: PlayerId,
+    version: u32,
+}
+
+#[post("/picks/history/restore")]
+pub async fn restore_picks(
+    path: web::Path<BashoId>,
+    form: web::Form<RestorePicksFormData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let basho_id = path.into_inner();
+    let mut db = state.db.lock().unwrap();
+    BaseTemplate::for_admin(&db, &identity, &state)?;
+    let player = Player::with_id(&db, form.player_id, basho_id)?
+        .ok_or_else(|| HandlerError::NotFound("player".to_string()))?;
+    data::basho::restore_player_picks(&mut db, player.id, basho_id, form.version)?;
+    Ok(HttpResponse::SeeOther()
+        .insert_header((
+            http::header::LOCATION,
+            format!(
+                "{}/picks/history?player={}",
+                basho_id.url_path(),
+                player.name
+            ),
+        ))
+        .finish())
+}
@@ -0,0 +1,106 @@
+use actix_identity::Identity;
+use actix_web::{get, http, post, web, HttpResponse, Responder};
+use askama::Template;
+use askama_web::WebTemplate;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use super::{BaseTemplate, HandlerError, IdentityExt, Result};
+use crate::data::bout_prediction::{self, PredictionStanding, ScheduledBout};
+use crate::data::{BashoId, BashoInfo, DataError, Day, RikishiId};
+use crate::AppState;
+
+#[derive(Template, WebTemplate)]
+#[template(path = "bout_predictions.html")]
+pub struct BoutPredictionsTemplate {
+    base: BaseTemplate,
+    basho: BashoInfo,
+    /// The next day still open for predictions, with its bouts.
+    open_day: Option<(Day, DateTime<Utc>, Vec<ScheduledBout>)>,
+    scored_days: Vec<Day>,
+    /// The scored day being viewed, with its bouts and standings.
+    results_day: Option<(Day, Vec<ScheduledBout>, Vec<PredictionStanding>)>,
+    standings: Vec<PredictionStanding>,
+}
+
+impl BoutPredictionsTemplate {
+    fn is_self(&self, standing: &PredictionStanding) -> bool {
+        self.base
+            .player
+            .as_ref()
+            .is_some_and(|p| p.id == standing.player.id)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PredictionsQuery {
+    day: Option<Day>,
+}
+
+#[get("/predictions")]
+pub async fn predictions_page(
+    path: web::Path<BashoId>,
+    query: web::Query<PredictionsQuery>,
+    state: web::Data<AppState>,
+    identity: Option<Identity>,
+) -> Result<BoutPredictionsTemplate> {
+    let basho_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
+    let basho = BashoInfo::with_id(&db, basho_id)?
+        .ok_or_else(|| HandlerError::NotFound("basho".to_string()))?;
+    let player_id = base.player.as_ref().map(|p| p.id);
+
+    let open_day = match bout_prediction::next_open_day(&db, basho_id, basho.start_date)? {
+        Some(day) => Some((
+            day,
+            bout_prediction::day_deadline(basho.start_date, day),
+            ScheduledBout::for_day(&db, basho_id, day, player_id)?,
+        )),
+        None => None,
+    };
+    let scored_days = bout_prediction::scored_days(&db, basho_id)?;
+    let results_day = match query.day.or_else(|| scored_days.last().copied()) {
+        Some(day) if scored_days.contains(&day) => Some((
+            day,
+            ScheduledBout::for_day(&db, basho_id, day, player_id)?,
+            PredictionStanding::for_basho(&db, basho_id, Some(day))?,
+        )),
+        Some(_) => return Err(HandlerError::NotFound("day".to_string())),
+        None => None,
+    };
+    let standings = PredictionStanding::for_basho(&db, basho_id, None)?;
+    Ok(BoutPredictionsTemplate {
+        base,
+        basho,
+        open_day,
+        scored_days,
+        results_day,
+        standings,
+    })
+}
+
+/// Form fields are `bout-{seq}` with the predicted winner's rikishi id as the value; other fields are ignored.
+#[post("/predictions/{day}")]
+pub async fn save_predictions(
+    path: web::Path<(BashoId, Day)>,
+    form: web::Form<HashMap<String, String>>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let (basho_id, day) = path.into_inner();
+    let player_id = identity.player_id()?;
+    let winners = form
+        .iter()
+        .filter(|(key, _)| key.starts_with("bout-"))
+        .map(|(_, id)| id.parse::<RikishiId>().map_err(|_| DataError::InvalidPicks))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut db = state.db.lock().unwrap();
+    bout_prediction::save_predictions(&mut db, player_id, basho_id, day, &winners)?;
+    Ok(HttpResponse::SeeOther()
+        .insert_header((
+            http::header::LOCATION,
+            format!("{}/predictions", basho_id.url_path()),
+        ))
+        .finish())
+}
@@ -0,0 +1,321 @@
+use actix_web::http::header::CACHE_CONTROL;
+use actix_web::{get, web, HttpResponse};
+use askama::Template;
+use itertools::Itertools;
+
+use super::{HandlerError, Result};
+use crate::data::player::BashoScore;
+use crate::data::{BashoId, BashoInfo, Player, Rank, RankName, RankSide};
+use crate::AppState;
+
+/// The most players that can be compared on one chart.
+pub const MAX_PLAYERS: usize = 5;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 240.0;
+const PLOT_LEFT: f64 = 40.0;
+const PLOT_RIGHT: f64 = WIDTH - 10.0;
+const PLOT_TOP: f64 = 10.0;
+const PLOT_BOTTOM: f64 = HEIGHT - 40.0;
+const LEGEND_SPACING: f64 = 120.0;
+const COLORS: [&str; MAX_PLAYERS] = ["#c0392b", "#2471a3", "#229954", "#d68910", "#7d3c98"];
+
+/// Rank names from the top down, with how many numbered ranks each usually has. On the rank chart every name gets the same height so that Sanyaku stays readable next to the much larger lower divisions.
+const RANK_BANDS: [(RankName, u16); 11] = [
+    (RankName::Yokozuna, 2),
+    (RankName::Ozeki, 3),
+    (RankName::Sekiwake, 1),
+    (RankName::Komusubi, 1),
+    (RankName::Maegashira, 17),
+    (RankName::Juryo, 14),
+    (RankName::Makushita, 60),
+    (RankName::Sandanme, 90),
+    (RankName::Jonidan, 100),
+    (RankName::Jonokuchi, 100),
+    (RankName::BanzukeGai, 1),
+];
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartKind {
+    Rank,
+    Score,
+    Place,
+}
+
+impl ChartKind {
+    fn title(self) -> &'static str {
+        match self {
+            ChartKind::Rank => "Rank",
+            ChartKind::Score => "Score",
+            ChartKind::Place => "Place",
+        }
+    }
+
+    fn value(self, score: &BashoScore) -> Option<f64> {
+        match self {
+            ChartKind::Rank => score.rank.map(rank_value),
+            ChartKind::Score => score.wins.map(f64::from),
+            ChartKind::Place => score.place.map(f64::from),
+        }
+    }
+
+    fn label(self, score: &BashoScore) -> String {
+        match self {
+            ChartKind::Rank => score.rank.map(|r| format!("{r:#}")).unwrap_or_default(),
+            ChartKind::Score => score.wins.map(|w| format!("{w} wins")).unwrap_or_default(),
+            ChartKind::Place => score.place.map(|p| format!("#{p}")).unwrap_or_default(),
+        }
+    }
+}
+
+fn rank_value(rank: Rank) -> f64 {
+    let band = RANK_BANDS
+        .iter()
+        .position(|(name, _)| *name == rank.name)
+        .unwrap();
+    let slots = RANK_BANDS[band].1;
+    let slot = (rank.number.max(1) - 1) * 2 + u16::from(rank.side == RankSide::West);
+    band as f64 + (slot as f64 / (2 * slots) as f64).min(0.99)
+}
+
+struct Axis {
+    min: f64,
+    max: f64,
+    /// Whether smaller values are better and so drawn at the top.
+    inverted: bool,
+    ticks: Vec<(f64, String)>,
+}
+
+impl Axis {
+    fn for_values(kind: ChartKind, values: impl Iterator<Item = f64>) -> Self {
+        let (min, max) = values.minmax().into_option().unwrap_or((0.0, 1.0));
+        match kind {
+            ChartKind::Rank => {
+                let (min, max) = (min.floor(), max.floor() + 1.0);
+                Axis {
+                    min,
+                    max,
+                    inverted: true,
+                    ticks: (min as usize..max as usize)
+                        .map(|band| (band as f64, RANK_BANDS[band].0.to_string()))
+                        .collect(),
+                }
+            }
+            ChartKind::Score => {
+                let max = (max / 10.0).ceil().max(1.0) * 10.0;
+                Axis {
+                    min: 0.0,
+                    max,
+                    inverted: false,
+                    ticks: (0..=max as usize)
+                        .step_by(10)
+                        .map(|v| (v as f64, v.to_string()))
+                        .collect(),
+                }
+            }
+            ChartKind::Place => {
+                let step = nice_step(max / 4.0);
+                let max = (max / step).ceil().max(1.0) * step;
+                Axis {
+                    min: 1.0,
+                    max,
+                    inverted: true,
+                    ticks: std::iter::once(1.0)
+                        .chain((1..=(max / step) as usize).map(|i| i as f64 * step))
+                        .dedup()
+                        .map(|v| (v, format!("#{v}")))
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        let frac = if self.max > self.min {
+            (value - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        };
+        if self.inverted {
+            PLOT_TOP + frac * (PLOT_BOTTOM - PLOT_TOP)
+        } else {
+            PLOT_BOTTOM - frac * (PLOT_BOTTOM - PLOT_TOP)
+        }
+    }
+}
+
+/// Rounds up to 1, 2 or 5 times a power of ten.
+fn nice_step(rough: f64) -> f64 {
+    let magnitude = 10f64.powf(rough.max(1.0).log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap()
+}
+
+pub struct ChartPoint {
+    x: f64,
+    y: f64,
+    label: String,
+}
+
+pub struct ChartSeries {
+    name: String,
+    color: &'static str,
+    legend_x: f64,
+    /// SVG path data, broken into separate runs across basho the player skipped.
+    path: String,
+    points: Vec<ChartPoint>,
+}
+
+#[derive(Template)]
+#[template(path = "chart.svg", escape = "html")]
+pub struct ChartTemplate {
+    title: String,
+    width: f64,
+    height: f64,
+    plot_left: f64,
+    plot_right: f64,
+    plot_bottom: f64,
+    x_ticks: Vec<(f64, String)>,
+    y_ticks: Vec<(f64, String)>,
+    series: Vec<ChartSeries>,
+}
+
+impl ChartTemplate {
+    fn new(kind: ChartKind, histories: &[(String, Vec<BashoScore>)]) -> Self {
+        let basho_ids: Vec<BashoId> = histories
+            .iter()
+            .flat_map(|(_, scores)| scores.iter())
+            .filter(|s| kind.value(s).is_some())
+            .map(|s| s.basho_id)
+            .sorted()
+            .dedup()
+            .collect();
+        let x = |basho_id: BashoId| {
+            let i = basho_ids.binary_search(&basho_id).unwrap_or(0) as f64;
+            if basho_ids.len() > 1 {
+                PLOT_LEFT + i / (basho_ids.len() - 1) as f64 * (PLOT_RIGHT - PLOT_LEFT)
+            } else {
+                (PLOT_LEFT + PLOT_RIGHT) / 2.0
+            }
+        };
+        let axis = Axis::for_values(
+            kind,
+            histories
+                .iter()
+                .flat_map(|(_, scores)| scores.iter())
+                .filter_map(|s| kind.value(s)),
+        );
+
+        let series = histories
+            .iter()
+            .zip(COLORS)
+            .enumerate()
+            .map(|(i, ((name, scores), color))| {
+                let mut path = String::new();
+                let mut points = vec![];
+                let mut pen_down = false;
+                // Scores are newest first; draw oldest to newest.
+                for score in scores.iter().rev() {
+                    if !basho_ids.contains(&score.basho_id) {
+                        continue;
+                    }
+                    let Some(value) = kind.value(score) else {
+                        pen_down = false;
+                        continue;
+                    };
+                    let point = ChartPoint {
+                        x: x(score.basho_id),
+                        y: axis.y(value),
+                        label: format!("{name} {:#}: {}", score.basho_id, kind.label(score)),
+                    };
+                    path.push_str(&format!(
+                        "{}{:.1},{:.1} ",
+                        if pen_down { "L" } else { "M" },
+                        point.x,
+                        point.y
+                    ));
+                    pen_down = true;
+                    points.push(point);
+                }
+                ChartSeries {
+                    name: name.to_owned(),
+                    color,
+                    legend_x: PLOT_LEFT + i as f64 * LEGEND_SPACING,
+                    path,
+                    points,
+                }
+            })
+            .collect();
+
+        ChartTemplate {
+            title: format!(
+                "{} history: {}",
+                kind.title(),
+                histories.iter().map(|(name, _)| name).join(", ")
+            ),
+            width: WIDTH,
+            height: HEIGHT,
+            plot_left: PLOT_LEFT,
+            plot_right: PLOT_RIGHT,
+            plot_bottom: PLOT_BOTTOM,
+            x_ticks: basho_ids
+                .iter()
+                .filter(|id| id.month == 1)
+                .map(|id| (x(*id), id.year.to_string()))
+                .collect(),
+            y_ticks: axis
+                .ticks
+                .iter()
+                .map(|(value, label)| (axis.y(*value), label.to_owned()))
+                .collect(),
+            series,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ChartQuery {
+    /// Comma-separated player names.
+    players: String,
+}
+
+#[get("/chart/{kind}.svg")]
+pub async fn chart_svg(
+    path: web::Path<ChartKind>,
+    query: web::Query<ChartQuery>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let kind = path.into_inner();
+    let histories = {
+        let db = state.db.lock().unwrap();
+        let rank_for_basho = BashoInfo::current_or_next_basho_id(&db)?;
+        query
+            .players
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unique()
+            .take(MAX_PLAYERS)
+            .map(|name| -> Result<(String, Vec<BashoScore>)> {
+                let player = Player::with_name(&db, name.to_string(), rank_for_basho)?
+                    .ok_or_else(|| HandlerError::NotFound("player".to_string()))?;
+                let scores = BashoScore::with_player_id(&db, player.id, &player.name, None)?;
+                Ok((player.name, scores))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    if histories.is_empty() {
+        return Err(HandlerError::NotFound("player".to_string()));
+    }
+    let svg = ChartTemplate::new(kind, &histories)
+        .render()
+        .map_err(anyhow::Error::from)?;
+    Ok(HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header((CACHE_CONTROL, "max-age=600"))
+        .body(svg))
+}
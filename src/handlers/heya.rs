@@ -1,22 +1,103 @@
 use actix_identity::Identity;
-use actix_web::{get, http, post, web, HttpResponse, Responder};
+use actix_web::{get, http, post, web, Either, HttpRequest, HttpResponse, Responder};
 use askama::Template;
 use askama_web::WebTemplate;
 use rusqlite::Connection;
+use std::collections::HashSet;
 
-use crate::data::heya::{HOST_MAX, JOIN_MAX, MEMBER_MAX};
-use crate::data::{Heya, PlayerId};
+use crate::data::heya::{
+    HeyaBashoResult, HeyaId, HeyaInvite, JoinRequest, HOST_MAX, INVITE_DAYS, JOIN_MAX, MEMBER_MAX,
+    MESSAGE_LENGTH, MESSAGE_RATE_MAX, MESSAGE_RATE_MINUTES,
+};
+use crate::data::heya_draft::{DraftStanding, HeyaDraft};
+use crate::data::heya_post::{HeyaPost, HeyaPostId, POST_LENGTH};
+use crate::data::push::{notify_each, PushType};
+use crate::data::{
+    BashoId, BashoInfo, BashoRikishi, DataError, FetchBashoRikishi, Heya, PlayerId, RikishiId,
+};
 use crate::handlers::{HandlerError, IdentityExt};
 use crate::AppState;
 
 use super::{BaseTemplate, Result};
 
+/// Looks up the heya for the `{heya_id}` path segment, which may be the current slug, an old slug from before a rename, or a numeric ID. Form posts use this directly, since redirecting them would drop the form.
+fn lookup_heya(db: &Connection, segment: &str, include_members: bool) -> Result<Heya> {
+    match Heya::with_slug(db, segment, include_members) {
+        Err(DataError::HeyaNotFound { .. }) => (),
+        result => return Ok(result?),
+    }
+    Ok(match segment.parse::<HeyaId>() {
+        Ok(id) => Heya::with_id(db, id, include_members)?,
+        Err(_) => Heya::with_old_slug(db, segment, include_members)?,
+    })
+}
+
+/// Resolves the `{heya_id}` path segment for pages. The current slug is served directly, while a numeric ID or an old slug redirects to the same page under the current slug.
+fn find_heya(
+    db: &Connection,
+    req: &HttpRequest,
+    segment: &str,
+    include_members: bool,
+) -> Result<Either<Heya, HttpResponse>> {
+    let heya = lookup_heya(db, segment, include_members)?;
+    if heya.slug == segment {
+        return Ok(Either::Left(heya));
+    }
+    let mut redirect = if segment.parse::<HeyaId>().is_ok() {
+        HttpResponse::TemporaryRedirect()
+    } else {
+        HttpResponse::MovedPermanently()
+    };
+    let rest = req.path().trim_start_matches("/heya/");
+    let mut location = format!(
+        "{}{}",
+        heya.url_path(),
+        rest.find('/').map_or("", |i| &rest[i..])
+    );
+    if !req.query_string().is_empty() {
+        location.push('?');
+        location.push_str(req.query_string());
+    }
+    Ok(Either::Right(
+        redirect
+            .insert_header((http::header::LOCATION, location))
+            .finish(),
+    ))
+}
+
 #[derive(Template, WebTemplate)]
 #[template(path = "heya.html")]
 pub struct HeyaTemplate {
     base: BaseTemplate,
     heya: Heya,
     is_oyakata: bool,
+    is_cohost: bool,
+    is_member: bool,
+    has_join_request: bool,
+    invite: Option<HeyaInvite>,
+    invite_invalid: bool,
+    invites: Vec<HeyaInvite>,
+    join_requests: Vec<JoinRequest>,
+    thread: Option<BashoId>,
+    threads: Vec<BashoId>,
+    posts: Vec<HeyaPost>,
+}
+
+impl HeyaTemplate {
+    fn can_delete_post(&self, post: &HeyaPost) -> bool {
+        self.is_oyakata
+            || self
+                .base
+                .player
+                .as_ref()
+                .is_some_and(|p| p.id == post.player.id)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    invite: Option<String>,
+    thread: Option<BashoId>,
 }
 
 #[get("")]
@@ -24,19 +105,203 @@ pub async fn page(
     state: web::Data<AppState>,
     identity: Option<Identity>,
     path: web::Path<String>,
-) -> Result<HeyaTemplate> {
+    query: web::Query<PageQuery>,
+    req: HttpRequest,
+) -> Result<Either<HeyaTemplate, HttpResponse>> {
     let db = state.db.lock().unwrap();
+    let mut heya = match find_heya(&db, &req, &path, true)? {
+        Either::Left(heya) => heya,
+        Either::Right(redirect) => return Ok(Either::Right(redirect)),
+    };
     let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
     let player_id = identity.and_then(|i| i.player_id().ok());
-    let mut heya = Heya::with_slug(&db, &path, true)?;
     for m in heya.members.as_mut().unwrap() {
         m.is_self = player_id.is_some_and(|id| id == m.player.id);
     }
-    Ok(HeyaTemplate {
-        is_oyakata: player_id == Some(heya.oyakata.id),
+    let is_oyakata = player_id == Some(heya.oyakata.id);
+    let is_member = heya.members.as_ref().unwrap().iter().any(|m| m.is_self);
+    let is_cohost = heya
+        .members
+        .as_ref()
+        .unwrap()
+        .iter()
+        .any(|m| m.is_self && m.is_cohost);
+    let invite = match &query.invite {
+        Some(token) => heya.valid_invite(&db, token)?,
+        None => None,
+    };
+    let (threads, posts) = if is_member {
+        let mut threads = HeyaPost::basho_threads(&db, heya.id)?;
+        if !threads.contains(&base.current_or_next_basho_id) {
+            threads.insert(0, base.current_or_next_basho_id);
+        }
+        (threads, HeyaPost::in_thread(&db, heya.id, query.thread)?)
+    } else {
+        (vec![], vec![])
+    };
+    let (invites, join_requests) = if is_oyakata {
+        (heya.invites(&db)?, heya.join_requests(&db)?)
+    } else {
+        (vec![], vec![])
+    };
+    Ok(Either::Left(HeyaTemplate {
+        is_oyakata,
+        is_cohost,
+        is_member,
+        has_join_request: match player_id {
+            Some(id) if !is_member => heya.has_join_request(&db, id)?,
+            _ => false,
+        },
+        invite_invalid: query.invite.is_some() && invite.is_none(),
+        invite,
+        invites,
+        join_requests,
+        thread: query.thread,
+        threads,
+        posts,
         base,
         heya,
-    })
+    }))
+}
+
+#[derive(Template, WebTemplate)]
+#[template(path = "heya_history.html")]
+pub struct HeyaHistoryTemplate {
+    base: BaseTemplate,
+    heya: Heya,
+    history: Vec<HeyaBashoResult>,
+}
+
+#[get("/history")]
+pub async fn history(
+    state: web::Data<AppState>,
+    identity: Option<Identity>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Either<HeyaHistoryTemplate, HttpResponse>> {
+    let db = state.db.lock().unwrap();
+    let heya = match find_heya(&db, &req, &path, false)? {
+        Either::Left(heya) => heya,
+        Either::Right(redirect) => return Ok(Either::Right(redirect)),
+    };
+    let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
+    let history = HeyaBashoResult::for_heya(&db, heya.id)?;
+    Ok(Either::Left(HeyaHistoryTemplate {
+        base,
+        heya,
+        history,
+    }))
+}
+
+#[derive(Template, WebTemplate)]
+#[template(path = "heya_draft.html")]
+pub struct HeyaDraftTemplate {
+    base: BaseTemplate,
+    heya: Heya,
+    basho: BashoInfo,
+    draft: Option<HeyaDraft>,
+    /// Rikishi nobody has drafted yet, in banzuke order.
+    available: Vec<BashoRikishi>,
+    standings: Vec<DraftStanding>,
+    is_member: bool,
+    can_manage: bool,
+}
+
+impl HeyaDraftTemplate {
+    fn is_on_the_clock(&self) -> bool {
+        let on_the_clock = self.draft.as_ref().and_then(|d| d.on_the_clock());
+        on_the_clock.is_some_and(|p| self.base.player.as_ref().is_some_and(|me| me.id == p.id))
+    }
+
+    fn can_start(&self) -> bool {
+        self.can_manage && self.draft.is_none() && !self.basho.has_started()
+    }
+}
+
+#[get("/draft")]
+pub async fn draft(
+    state: web::Data<AppState>,
+    identity: Option<Identity>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Either<HeyaDraftTemplate, HttpResponse>> {
+    let db = state.db.lock().unwrap();
+    let heya = match find_heya(&db, &req, &path, true)? {
+        Either::Left(heya) => heya,
+        Either::Right(redirect) => return Ok(Either::Right(redirect)),
+    };
+    let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
+    let basho_id = base.current_or_next_basho_id;
+    let basho = BashoInfo::with_id(&db, basho_id)?
+        .ok_or_else(|| HandlerError::NotFound("basho".to_string()))?;
+    let draft = HeyaDraft::for_heya(&db, heya.id, basho_id)?;
+    let (available, standings) = match &draft {
+        Some(draft) => (
+            FetchBashoRikishi::with_db(&db, basho_id, &HashSet::new())?
+                .by_rank
+                .into_iter()
+                .flat_map(|rr| rr.east.into_iter().chain(rr.west))
+                .filter(|r| !r.is_kyujyo && !draft.picks.iter().any(|p| p.rikishi_id == r.id))
+                .collect(),
+            DraftStanding::for_draft(&db, heya.id, basho_id)?,
+        ),
+        None => (vec![], vec![]),
+    };
+    let player_id = base.player.as_ref().map(|p| p.id);
+    Ok(Either::Left(HeyaDraftTemplate {
+        is_member: heya
+            .members
+            .as_ref()
+            .unwrap()
+            .iter()
+            .any(|m| Some(m.player.id) == player_id),
+        can_manage: match player_id {
+            Some(player_id) => heya.can_manage_members(&db, player_id)?,
+            None => false,
+        },
+        base,
+        heya,
+        basho,
+        draft,
+        available,
+        standings,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DraftData {
+    start_pick_minutes: Option<i64>,
+    rikishi_id: Option<RikishiId>,
+}
+
+#[post("/draft")]
+pub async fn draft_action(
+    path: web::Path<String>,
+    data: web::Form<DraftData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let player_id = identity.player_id()?;
+    let mut db = state.db.lock().unwrap();
+    let heya = lookup_heya(&db, &path, false)?;
+    let basho_id = BashoInfo::current_or_next_basho_id(&db)?;
+    // Nobody may be around when a turn's timer runs out, so expired turns are caught up on the next post, which may be an empty one from the page's continue button.
+    let txn = db.transaction()?;
+    HeyaDraft::auto_draft_expired(&txn, heya.id, basho_id, false)?;
+    txn.commit()?;
+    if let Some(pick_minutes) = data.start_pick_minutes {
+        if !heya.can_manage_members(&db, player_id)? {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+        HeyaDraft::start(&mut db, heya.id, basho_id, pick_minutes)?;
+    }
+    if let Some(rikishi_id) = data.rikishi_id {
+        HeyaDraft::make_pick(&mut db, heya.id, basho_id, player_id, rikishi_id)?;
+    }
+
+    Ok(HttpResponse::SeeOther()
+        .insert_header((http::header::LOCATION, format!("{}/draft", heya.url_path())))
+        .finish())
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,6 +309,15 @@ pub struct EditData {
     set_name: Option<String>,
     add_player_id: Option<PlayerId>,
     delete_player_id: Option<PlayerId>,
+    create_invite: Option<String>,
+    revoke_invite: Option<String>,
+    join_invite: Option<String>,
+    request_join: Option<String>,
+    approve_player_id: Option<PlayerId>,
+    decline_player_id: Option<PlayerId>,
+    cohost_player_id: Option<PlayerId>,
+    uncohost_player_id: Option<PlayerId>,
+    transfer_player_id: Option<PlayerId>,
 }
 
 #[post("")]
@@ -53,42 +327,254 @@ pub async fn edit(
     state: web::Data<AppState>,
     identity: Identity,
 ) -> Result<impl Responder> {
-    let mut db = state.db.lock().unwrap();
-    let mut heya = Heya::with_slug(&db, &path, false)?;
-    apply_edit_actions(&mut heya, &mut db, data.0, identity.player_id()?)?;
+    let (location, push_types) = {
+        let mut db = state.db.lock().unwrap();
+        let mut heya = lookup_heya(&db, &path, false)?;
+        let push_types = apply_edit_actions(&mut heya, &mut db, data.0, identity.player_id()?)?;
+        let updated_heya = Heya::with_id(&db, heya.id, false)?;
+        (updated_heya.url_path(), push_types)
+    };
+    notify_each(&state.db, &state.push, &state.config.url(), &push_types).await?;
 
-    let updated_heya = Heya::with_id(&db, heya.id, false)?;
     Ok(HttpResponse::SeeOther()
-        .insert_header((http::header::LOCATION, updated_heya.url_path()))
+        .insert_header((http::header::LOCATION, location))
         .finish())
 }
 
+/// Returns notifications for the players affected by the edit.
 fn apply_edit_actions(
     heya: &mut Heya,
     db: &mut Connection,
     data: EditData,
     user: PlayerId,
-) -> Result<()> {
+) -> Result<Vec<PushType>> {
+    let mut push_types = vec![];
     if let Some(name) = data.set_name {
         heya.set_name(db, &name)?;
     }
     if let Some(player_id) = data.add_player_id {
-        if heya.oyakata.id == user {
+        if heya.can_manage_members(db, user)? {
             heya.add_member(db, player_id)?;
+            push_types.push(PushType::HeyaAdded(heya.id, player_id));
         } else {
             return Err(HandlerError::MustBeLoggedIn);
         }
     }
     if let Some(player_id) = data.delete_player_id {
-        // Member can choose to leave; oyakata can kick others out:
-        if heya.oyakata.id == user || player_id == user {
+        // Member can choose to leave; oyakata can kick others out, and co-hosts can kick out
+        // members who aren't co-hosts themselves:
+        if heya.oyakata.id == user
+            || player_id == user
+            || (heya.is_cohost(db, user)? && !heya.is_cohost(db, player_id)?)
+        {
             heya.delete_member(db, player_id)?;
+            if player_id != user {
+                push_types.push(PushType::HeyaRemoved(heya.id, player_id));
+            }
+        } else {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+    }
+    if data.create_invite.is_some() {
+        if heya.oyakata.id == user {
+            heya.create_invite(db, user)?;
+        } else {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+    }
+    if let Some(token) = data.revoke_invite {
+        if heya.oyakata.id == user {
+            heya.revoke_invite(db, &token)?;
+        } else {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+    }
+    if let Some(token) = data.join_invite {
+        heya.join_with_invite(db, &token, user)?;
+    }
+    if data.request_join.is_some() {
+        heya.request_join(db, user)?;
+    }
+    if let Some(player_id) = data.approve_player_id {
+        if heya.oyakata.id == user {
+            heya.approve_join_request(db, player_id)?;
+            push_types.push(PushType::HeyaAdded(heya.id, player_id));
+        } else {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+    }
+    if let Some(player_id) = data.decline_player_id {
+        // Requester can withdraw; oyakata can decline:
+        if heya.oyakata.id == user || player_id == user {
+            heya.decline_join_request(db, player_id)?;
+        } else {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+    }
+    if let Some(player_id) = data.cohost_player_id {
+        if heya.oyakata.id == user {
+            heya.set_cohost(db, player_id, true)?;
+        } else {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+    }
+    if let Some(player_id) = data.uncohost_player_id {
+        // Co-host can step down; oyakata can demote:
+        if heya.oyakata.id == user || player_id == user {
+            heya.set_cohost(db, player_id, false)?;
+        } else {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+    }
+    if let Some(player_id) = data.transfer_player_id {
+        if heya.oyakata.id == user {
+            heya.transfer_ownership(db, player_id)?;
         } else {
             return Err(HandlerError::MustBeLoggedIn);
         }
     }
 
-    Ok(())
+    Ok(push_types)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MessageData {
+    message: String,
+}
+
+#[post("/message")]
+pub async fn message(
+    path: web::Path<String>,
+    data: web::Form<MessageData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let player_id = identity.player_id()?;
+    let (location, push_type) = {
+        let mut db = state.db.lock().unwrap();
+        let heya = lookup_heya(&db, &path, false)?;
+        if heya.oyakata.id != player_id {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+        let message = data.message.trim();
+        Heya::record_message(&mut db, heya.id, player_id, message)?;
+        (
+            heya.url_path(),
+            PushType::HeyaMessage(heya.id, message.to_string()),
+        )
+    };
+    let stats = notify_each(&state.db, &state.push, &state.config.url(), &[push_type]).await?;
+    info!("heya message sent: {:?}", stats);
+
+    Ok(HttpResponse::SeeOther()
+        .insert_header((http::header::LOCATION, location))
+        .finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostData {
+    body: String,
+    thread: Option<BashoId>,
+}
+
+#[post("/posts")]
+pub async fn create_post(
+    path: web::Path<String>,
+    data: web::Form<PostData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let player_id = identity.player_id()?;
+    let (location, push_type) = {
+        let mut db = state.db.lock().unwrap();
+        let heya = lookup_heya(&db, &path, true)?;
+        if !heya
+            .members
+            .as_ref()
+            .unwrap()
+            .iter()
+            .any(|m| m.player.id == player_id)
+        {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+        let post_id = HeyaPost::create(&mut db, heya.id, player_id, data.thread, &data.body)?;
+        let post = HeyaPost::with_id(&db, post_id)?
+            .ok_or_else(|| HandlerError::NotFound("post".to_string()))?;
+        (post.url_path(&heya.slug), PushType::HeyaPost(post_id))
+    };
+    notify_each(&state.db, &state.push, &state.config.url(), &[push_type]).await?;
+
+    Ok(HttpResponse::SeeOther()
+        .insert_header((http::header::LOCATION, location))
+        .finish())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostAction {
+    Pin,
+    Unpin,
+    Delete,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostActionData {
+    action: PostAction,
+}
+
+#[post("/posts/{post_id}")]
+pub async fn edit_post(
+    path: web::Path<(String, HeyaPostId)>,
+    data: web::Form<PostActionData>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let (slug, post_id) = path.into_inner();
+    let player_id = identity.player_id()?;
+    let db = state.db.lock().unwrap();
+    let heya = lookup_heya(&db, &slug, false)?;
+    let mut post = HeyaPost::with_id(&db, post_id)?
+        .filter(|post| post.heya_id == heya.id)
+        .ok_or_else(|| HandlerError::NotFound("post".to_string()))?;
+    let location = post.url_path(&heya.slug);
+    match data.action {
+        PostAction::Pin | PostAction::Unpin if heya.oyakata.id == player_id => {
+            post.set_pinned(&db, matches!(data.action, PostAction::Pin))?;
+        }
+        // Oyakata can delete any post; members can delete their own:
+        PostAction::Delete if heya.oyakata.id == player_id || post.player.id == player_id => {
+            post.delete(&db)?;
+        }
+        _ => return Err(HandlerError::MustBeLoggedIn),
+    }
+
+    Ok(HttpResponse::SeeOther()
+        .insert_header((http::header::LOCATION, location))
+        .finish())
+}
+
+#[post("/dissolve")]
+pub async fn dissolve(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let push_types = {
+        let mut db = state.db.lock().unwrap();
+        let heya = lookup_heya(&db, &path, false)?;
+        if heya.oyakata.id != identity.player_id()? {
+            return Err(HandlerError::MustBeLoggedIn);
+        }
+        let heya_name = heya.name.clone();
+        heya.dissolve(&mut db)?
+            .into_iter()
+            .map(|player_id| PushType::HeyaDissolved(heya_name.clone(), player_id))
+            .collect::<Vec<_>>()
+    };
+    notify_each(&state.db, &state.push, &state.config.url(), &push_types).await?;
+    Ok(HttpResponse::SeeOther()
+        .insert_header((http::header::LOCATION, "/heya"))
+        .finish())
 }
 
 #[derive(Template, WebTemplate)]
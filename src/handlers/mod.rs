@@ -10,10 +10,15 @@ use std::error::Error;
 use std::fmt::{Display, Formatter};
 
 pub mod admin;
+pub mod banzuke;
+pub mod banzuke_prediction;
 pub mod basho;
+pub mod bout_prediction;
+pub mod chart;
 pub mod heya;
 pub mod index;
 pub mod login;
+pub mod over_under;
 pub mod player;
 pub mod push;
 pub mod settings;
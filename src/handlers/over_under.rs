@@ -0,0 +1,80 @@
+use actix_identity::Identity;
+use actix_web::{get, http, post, web, HttpResponse, Responder};
+use askama::Template;
+use askama_web::WebTemplate;
+use std::collections::HashMap;
+
+use super::{BaseTemplate, HandlerError, IdentityExt, Result};
+use crate::data::over_under::{self, FeaturedRikishi, SeasonStanding};
+use crate::data::{BashoId, BashoInfo, DataError, RikishiId};
+use crate::AppState;
+
+#[derive(Template, WebTemplate)]
+#[template(path = "over_under.html")]
+pub struct OverUnderTemplate {
+    base: BaseTemplate,
+    basho: BashoInfo,
+    featured: Vec<FeaturedRikishi>,
+    season: Vec<SeasonStanding>,
+}
+
+impl OverUnderTemplate {
+    fn is_self(&self, standing: &SeasonStanding) -> bool {
+        self.base
+            .player
+            .as_ref()
+            .is_some_and(|p| p.id == standing.player.id)
+    }
+}
+
+#[get("/over-under")]
+pub async fn over_under_page(
+    path: web::Path<BashoId>,
+    state: web::Data<AppState>,
+    identity: Option<Identity>,
+) -> Result<OverUnderTemplate> {
+    let basho_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
+    let basho = BashoInfo::with_id(&db, basho_id)?
+        .ok_or_else(|| HandlerError::NotFound("basho".to_string()))?;
+    let featured = FeaturedRikishi::for_basho(&db, basho_id, base.player.as_ref().map(|p| p.id))?;
+    Ok(OverUnderTemplate {
+        season: SeasonStanding::for_year(&db, basho_id.year)?,
+        base,
+        basho,
+        featured,
+    })
+}
+
+/// Form fields are `rikishi-{id}` with a value of `over` or `under`; rikishi left unpicked are skipped.
+#[post("/over-under")]
+pub async fn save_picks(
+    path: web::Path<BashoId>,
+    form: web::Form<HashMap<String, String>>,
+    state: web::Data<AppState>,
+    identity: Identity,
+) -> Result<impl Responder> {
+    let basho_id = path.into_inner();
+    let player_id = identity.player_id()?;
+    let picks = form
+        .iter()
+        .filter_map(|(key, value)| {
+            key.strip_prefix("rikishi-")
+                .map(|id| (id.parse::<RikishiId>(), value.as_str()))
+        })
+        .map(|(id, value)| match (id, value) {
+            (Ok(id), "over") => Ok((id, true)),
+            (Ok(id), "under") => Ok((id, false)),
+            _ => Err(DataError::InvalidPicks),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut db = state.db.lock().unwrap();
+    over_under::save_picks(&mut db, player_id, basho_id, &picks)?;
+    Ok(HttpResponse::SeeOther()
+        .insert_header((
+            http::header::LOCATION,
+            format!("{}/over-under", basho_id.url_path()),
+        ))
+        .finish())
+}
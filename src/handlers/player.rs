@@ -4,6 +4,9 @@ use askama::Template;
 use askama_web::WebTemplate;
 
 use super::{BaseTemplate, HandlerError, Result};
+use crate::data::player::{HeadToHead, HeadToHeadRecord};
+use crate::data::player_stats::PlayerStats;
+use crate::data::rating::RatingHistoryPoint;
 use crate::data::Heya;
 use crate::data::{player::BashoScore, Player};
 use crate::handlers::IdentityExt;
@@ -15,6 +18,8 @@ pub struct PlayerTemplate {
     base: BaseTemplate,
     player: Player,
     basho_scores: Vec<BashoScore>,
+    stats: PlayerStats,
+    rating_history: Vec<RatingHistoryPoint>,
     recruit_heyas: Vec<Heya>,
 }
 
@@ -29,7 +34,10 @@ pub async fn player_page(
     let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
     let player = Player::with_name(&db, name, base.current_or_next_basho_id)?
         .ok_or_else(|| HandlerError::NotFound("player".to_string()))?;
-    let basho_scores = BashoScore::with_player_id(&db, player.id, &player.name)?;
+    let viewer_id = base.player.as_ref().map(|p| p.id);
+    let basho_scores = BashoScore::with_player_id(&db, player.id, &player.name, viewer_id)?;
+    let stats = PlayerStats::compute(&db, player.id, &basho_scores)?;
+    let rating_history = RatingHistoryPoint::for_player(&db, player.id)?;
 
     let recruit_heyas = identity
         .as_ref()
@@ -38,7 +46,9 @@ pub async fn player_page(
         .transpose()?
         .unwrap_or_default()
         .into_iter()
-        .filter(|heya| heya.oyakata.id == identity.as_ref().unwrap().player_id().unwrap())
+        .filter(|heya| {
+            heya.is_cohost || heya.oyakata.id == identity.as_ref().unwrap().player_id().unwrap()
+        })
         .filter(|hosted_heya| {
             !player
                 .heyas
@@ -53,6 +63,8 @@ pub async fn player_page(
         base,
         player,
         basho_scores,
+        stats,
+        rating_history,
         recruit_heyas,
     })
 }
@@ -65,3 +77,45 @@ impl PlayerTemplate {
             .is_some_and(|p| p.id == self.player.id)
     }
 }
+
+#[derive(Template, WebTemplate)]
+#[template(path = "player_vs.html")]
+pub struct PlayerVsTemplate {
+    base: BaseTemplate,
+    player: Player,
+    other: Player,
+    bashos: Vec<HeadToHead>,
+}
+
+#[get("/player/{player}/vs/{other}")]
+pub async fn player_vs(
+    path: web::Path<(String, String)>,
+    state: web::Data<AppState>,
+    identity: Option<Identity>,
+) -> Result<PlayerVsTemplate> {
+    let (name, other_name) = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
+    let player = Player::with_name(&db, name, base.current_or_next_basho_id)?
+        .ok_or_else(|| HandlerError::NotFound("player".to_string()))?;
+    let other = Player::with_name(&db, other_name, base.current_or_next_basho_id)?
+        .ok_or_else(|| HandlerError::NotFound("player".to_string()))?;
+    let viewer_id = base.player.as_ref().map(|p| p.id);
+    let bashos = HeadToHead::between(
+        BashoScore::with_player_id(&db, player.id, &player.name, viewer_id)?,
+        BashoScore::with_player_id(&db, other.id, &other.name, viewer_id)?,
+    );
+
+    Ok(PlayerVsTemplate {
+        base,
+        player,
+        other,
+        bashos,
+    })
+}
+
+impl PlayerVsTemplate {
+    fn record(&self) -> HeadToHeadRecord {
+        self.bashos.first().map(|b| b.record).unwrap_or_default()
+    }
+}
@@ -8,6 +8,7 @@ use askama_web::WebTemplate;
 
 use super::user_agent::UserAgent;
 use super::{BaseTemplate, HandlerError, Result};
+use crate::data::auto_pick::AutoPickStrategy;
 use crate::data::player::{self, Player, PlayerId};
 use crate::data::push::{PushTypeKey, Subscription};
 use crate::data::DbConn;
@@ -18,6 +19,7 @@ use crate::AppState;
 #[template(path = "settings.html")]
 pub struct SettingsTemplate {
     base: BaseTemplate,
+    auto_pick: Option<AutoPickStrategy>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,6 +27,8 @@ pub struct FormData {
     name: String,
     push_subscription: Option<web_push::SubscriptionInfo>,
     notification_opt_in: HashSet<PushTypeKey>,
+    #[serde(default)]
+    auto_pick: Option<AutoPickStrategy>,
 }
 
 #[get("/settings")]
@@ -34,8 +38,9 @@ pub async fn settings_page(
 ) -> Result<SettingsTemplate> {
     let db = state.db.lock().unwrap();
     let base = BaseTemplate::new(&db, Some(&identity), &state)?;
-    if base.player.is_some() {
-        Ok(SettingsTemplate { base })
+    if let Some(player) = &base.player {
+        let auto_pick = AutoPickStrategy::for_player(&db, player.id)?;
+        Ok(SettingsTemplate { base, auto_pick })
     } else {
         Err(HandlerError::MustBeLoggedIn)
     }
@@ -73,6 +78,7 @@ async fn settings_post_inner(
         let txn = db.transaction()?;
 
         Player::set_name(&txn, player_id, &form.name)?;
+        AutoPickStrategy::set_for_player(&txn, player_id, form.auto_pick)?;
 
         if let Some(subscription) = form.push_subscription {
             Subscription::register(
@@ -2,6 +2,7 @@ use std::ops::Range;
 
 use super::{BaseTemplate, IdentityExt, Result};
 use crate::data::leaders::HistoricLeader;
+use crate::data::rating::{PlayerRating, LEADERBOARD_MIN_BASHO};
 use crate::data::{BashoId, BashoInfo};
 use crate::AppState;
 use actix_identity::Identity;
@@ -18,6 +19,7 @@ pub struct StatsTemplate {
     leader_basho_count_options: Vec<usize>,
     leaders: Vec<HistoricLeader>,
     self_leader_index: Option<usize>,
+    ratings: Vec<PlayerRating>,
 }
 
 impl StatsTemplate {
@@ -41,6 +43,7 @@ pub struct QueryParams {
 
 const LEADER_BASHO_COUNT_OPTIONS: [usize; 3] = [6, 3, 2];
 const LEADERS_LIMIT: u32 = 5000;
+const RATINGS_LIMIT: u32 = 100;
 
 #[get("/stats")]
 pub async fn stats_page(
@@ -60,8 +63,10 @@ pub async fn stats_page(
         }
         None => None,
     };
+    let base = BaseTemplate::new(&db, identity.as_ref(), &state)?;
+    let ratings = PlayerRating::leaderboard(&db, base.current_or_next_basho_id, RATINGS_LIMIT)?;
     Ok(StatsTemplate {
-        base: BaseTemplate::new(&db, identity.as_ref(), &state)?,
+        base,
         basho_list,
         leader_basho_count,
         leader_basho_count_options: LEADER_BASHO_COUNT_OPTIONS
@@ -71,6 +76,7 @@ pub async fn stats_page(
             .collect(),
         leaders,
         self_leader_index,
+        ratings,
     })
 }
 
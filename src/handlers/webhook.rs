@@ -6,7 +6,7 @@ use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
 use anyhow::anyhow;
 
 use super::{BaseTemplate, Result};
-use crate::data::push::mass_notify_day_result;
+use crate::data::push::{mass_notify_day_result, mass_notify_heya_standings};
 use crate::external::sumo_api;
 use crate::AppState;
 
@@ -95,10 +95,22 @@ pub async fn receive_sumo_api(
         &state.config.webhook_secret,
     )?;
     if should_send_notifications {
-        let stats =
+        let mut stats =
             mass_notify_day_result(&state.db, &state.push, &state.config.url(), basho_id, day)
                 .await?;
+        stats.merge(
+            &mass_notify_heya_standings(&state.db, &state.push, &state.config.url(), basho_id, day)
+                .await?,
+        );
         info!("push notifications sent: {:?}", stats);
+
+        if day < 15 {
+            match sumo_api::query_and_schedule_sumo_api_torikumi(basho_id, day + 1, &state.db).await
+            {
+                Ok(count) => info!("scheduled {} bouts for day {}", count, day + 1),
+                Err(e) => warn!("failed to schedule day {} bouts: {}", day + 1, e),
+            }
+        }
     }
     Ok(HttpResponse::NoContent().finish())
 }
@@ -97,27 +97,50 @@ pub async fn run(app_state: &AppState) -> anyhow::Result<()> {
                     .service(handlers::push::trigger),
             )
             .service(handlers::stats::stats_page)
+            .service(handlers::banzuke::banzuke_page)
             .service(
                 web::scope("/basho/{basho_id}")
                     .service(handlers::basho::basho)
                     .service(handlers::basho::save_picks)
+                    .service(handlers::basho::pick_history)
+                    .service(handlers::basho::restore_picks)
+                    .service(handlers::bout_prediction::predictions_page)
+                    .service(handlers::bout_prediction::save_predictions)
+                    .service(handlers::banzuke_prediction::prediction_page)
+                    .service(handlers::banzuke_prediction::save_prediction)
+                    .service(handlers::over_under::over_under_page)
+                    .service(handlers::over_under::save_picks)
                     .service(handlers::admin::edit_basho_page)
                     .service(handlers::admin::edit_basho_post)
                     .service(handlers::admin::torikumi_page)
                     .service(handlers::admin::torikumi_post)
+                    .service(handlers::admin::schedule_torikumi)
                     .service(handlers::admin::finalize_basho)
+                    .service(handlers::admin::record_yusho)
+                    .service(handlers::admin::over_under_lines)
+                    .service(handlers::admin::bestow_award)
+                    .service(handlers::admin::revoke_award)
                     .service(handlers::admin::backfill_player_ranks),
             )
             .service(
                 web::scope("/heya/{heya_id}")
                     .service(handlers::heya::page)
-                    .service(handlers::heya::edit),
+                    .service(handlers::heya::edit)
+                    .service(handlers::heya::history)
+                    .service(handlers::heya::draft)
+                    .service(handlers::heya::draft_action)
+                    .service(handlers::heya::dissolve)
+                    .service(handlers::heya::message)
+                    .service(handlers::heya::create_post)
+                    .service(handlers::heya::edit_post),
             )
             .service(handlers::heya::create)
             .service(handlers::heya::list)
             .service(handlers::admin::admin_page)
             .service(handlers::admin::list_players)
             .service(handlers::player::player_page)
+            .service(handlers::player::player_vs)
+            .service(handlers::chart::chart_svg)
             .service(handlers::admin::update_user_images)
             .service(
                 web::scope("/webhook")